@@ -1,27 +1,36 @@
 //! Models for the main `osu.db` database file, which contains information on installed beatmaps.
 
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+    path::Path,
+};
 
 use flagset::{flags, FlagSet};
 use nom::{
     bytes::complete::tag,
     combinator::{cond, map},
     multi::length_count,
-    number::complete::{le_f32, le_f64, le_u16, le_u32, u8},
+    number::complete::{le_f32, le_f64, le_i16, le_u16, le_u32, u8},
     sequence::{preceded, tuple},
     IResult,
 };
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
 use crate::{
+    collections::CollectionListing,
     common::{
-        boolean, gameplay_mode, osu_string, windows_datetime, GameplayMode, Grade, Mods, OsuString,
+        boolean, check_version, gameplay_mode, osu_string, osu_string_size, version_to_date,
+        windows_datetime, write_boolean, write_osu_string, write_windows_datetime, GameplayMode,
+        Grade, Mods, OsuString,
     },
     error::Error,
+    scores::ScoreReplay,
 };
 
 /// Represents the `osu.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapListing {
     /// osu! version (e.g. 20150203)
     pub version: u32,
@@ -33,6 +42,7 @@ pub struct BeatmapListing {
     pub account_unlocked: bool,
 
     /// Date the account will be unlocked
+    #[cfg_attr(feature = "serde", serde(with = "time::serde::rfc3339"))]
     pub account_unlock_date: OffsetDateTime,
 
     /// Player name
@@ -46,7 +56,8 @@ pub struct BeatmapListing {
 }
 
 /// Represents a beatmap entry found in `osu.db`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapEntry {
     /// Size in bytes of the beatmap entry. Only present if version is less than 20191106.
     pub size: Option<u32>,
@@ -91,6 +102,7 @@ pub struct BeatmapEntry {
     pub spinner_count: u16,
 
     /// Last modification time, Windows ticks
+    #[cfg_attr(feature = "serde", serde(with = "time::serde::rfc3339"))]
     pub last_modification_time: OffsetDateTime,
 
     /// Approach rate. Byte if the version is less than 20140609, Single otherwise.
@@ -109,15 +121,23 @@ pub struct BeatmapEntry {
     pub slider_velocity: f64,
 
     /// Star Rating info for osu! standard. Only present if version is greater than or equal to 20140609.
+    ///
+    /// `None` if the section is absent from the file; `Some` with an empty list if present but no ratings were calculated.
     pub star_ratings_std: Option<Vec<StarRating>>,
 
     /// Star Rating info for Taiko. Only present if version is greater than or equal to 20140609.
+    ///
+    /// `None` if the section is absent from the file; `Some` with an empty list if present but no ratings were calculated.
     pub star_ratings_taiko: Option<Vec<StarRating>>,
 
     /// Star Rating info for CTB. Only present if version is greater than or equal to 20140609.
+    ///
+    /// `None` if the section is absent from the file; `Some` with an empty list if present but no ratings were calculated.
     pub star_ratings_ctb: Option<Vec<StarRating>>,
 
     /// Star Rating info for osu!mania. Only present if version is greater than or equal to 20140609.
+    ///
+    /// `None` if the section is absent from the file; `Some` with an empty list if present but no ratings were calculated.
     pub star_ratings_mania: Option<Vec<StarRating>>,
 
     /// Drain time, in seconds
@@ -153,8 +173,8 @@ pub struct BeatmapEntry {
     /// Grade achieved in osu!mania
     pub grade_mania: Grade,
 
-    /// Local beatmap offset
-    pub local_offset: u16,
+    /// Local beatmap offset, in milliseconds (can be negative)
+    pub local_offset: i16,
 
     /// Stack leniency
     pub stack_leniency: f32,
@@ -168,8 +188,8 @@ pub struct BeatmapEntry {
     /// Song tags
     pub song_tags: OsuString,
 
-    /// Online offset
-    pub online_offset: u16,
+    /// Online offset, in milliseconds (can be negative)
+    pub online_offset: i16,
 
     /// Font used for the title of the song
     pub font: OsuString,
@@ -178,6 +198,7 @@ pub struct BeatmapEntry {
     pub is_unplayed: bool,
 
     /// Last time when beatmap was played
+    #[cfg_attr(feature = "serde", serde(with = "time::serde::rfc3339"))]
     pub last_played: OffsetDateTime,
 
     /// Is the beatmap osz2
@@ -187,6 +208,7 @@ pub struct BeatmapEntry {
     pub folder_name: OsuString,
 
     /// Last time when beatmap was checked against osu! repository
+    #[cfg_attr(feature = "serde", serde(with = "time::serde::rfc3339"))]
     pub last_checked_online: OffsetDateTime,
 
     /// Ignore beatmap sound
@@ -214,8 +236,31 @@ pub struct BeatmapEntry {
     pub mania_scroll_speed: u8,
 }
 
+/// An iterator that lazily parses the beatmap entries in an `osu.db` file.
+///
+/// Iteration stops after the first entry that fails to parse.
+/// If the data ends before all of the announced entries have been parsed, [`Error::EntryCountMismatch`] is returned.
+#[derive(Clone, Debug)]
+pub struct BeatmapEntries<'a> {
+    input: &'a [u8],
+    version: u32,
+    parsed: usize,
+    remaining: usize,
+}
+
+/// A memory-mapped `osu.db` file, whose beatmap entries are parsed on demand.
+#[cfg(feature = "mmap")]
+pub struct MmapBeatmapListing {
+    mmap: memmap2::Mmap,
+}
+
 /// Represents the ranked status of a beatmap.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Statuses are ordered by stability, i.e. `Unknown < Unsubmitted < Pending < Ranked < Approved < Qualified < Loved`,
+/// so filters such as "at least Ranked" can be written as `status >= RankedStatus::Ranked`.
+/// This happens to match the order of the numeric values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RankedStatus {
     Unknown = 0,
     Unsubmitted = 1,
@@ -232,6 +277,7 @@ pub enum RankedStatus {
 
 /// Represents a star rating calculation for a particular mod combination.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StarRating {
     /// The mods used for this star rating
     pub mods: FlagSet<Mods>,
@@ -240,8 +286,32 @@ pub struct StarRating {
     pub rating: f64,
 }
 
+/// The per-beatmap settings stored in a beatmap entry. See [`BeatmapEntry::settings`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BeatmapSettings {
+    /// Is the beatmap osz2
+    pub is_osz2: bool,
+
+    /// Ignore beatmap sound
+    pub ignore_beatmap_hitsounds: bool,
+
+    /// Ignore beatmap skin
+    pub ignore_beatmap_skin: bool,
+
+    /// Disable storyboard
+    pub disable_storyboard: bool,
+
+    /// Disable video
+    pub disable_video: bool,
+
+    /// Visual override
+    pub visual_override: bool,
+}
+
 /// Represents a timing point found in `osu.db`.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimingPoint {
     /// The BPM of this timing point.
     pub bpm: f64,
@@ -281,11 +351,80 @@ impl std::fmt::Display for RankedStatus {
     }
 }
 
+impl RankedStatus {
+    /// Returns all ranked statuses, in the order of their numeric values.
+    pub fn all() -> [RankedStatus; 7] {
+        use RankedStatus::*;
+
+        [
+            Unknown,
+            Unsubmitted,
+            Pending,
+            Ranked,
+            Approved,
+            Qualified,
+            Loved,
+        ]
+    }
+}
+
+impl std::fmt::Display for UserPermissions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UserPermissions::*;
+
+        match self {
+            Normal => write!(f, "Normal"),
+            Moderator => write!(f, "Moderator"),
+            Supporter => write!(f, "Supporter"),
+            Friend => write!(f, "Friend"),
+            Peppy => write!(f, "Peppy"),
+            WorldCupStaff => write!(f, "World Cup Staff"),
+        }
+    }
+}
+
+impl std::fmt::Display for BeatmapEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_name_unicode(false))
+    }
+}
+
 impl BeatmapListing {
     /// Parses the contents of an `osu.db` file.
     pub fn from_bytes(data: &[u8]) -> Result<BeatmapListing, Error> {
-        let (_, listing) = beatmap_listing(data).map_err(|e| e.to_owned())?;
-        Ok(listing)
+        Self::from_bytes_with_progress(data, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Parses the contents of an `osu.db` file, reporting progress after each beatmap entry is parsed.
+    ///
+    /// The callback receives `(entries_parsed, total_entries)`, where the total is read from the start of the beatmap list.
+    /// Returning [`ControlFlow::Break`] stops parsing before the next entry, and [`Error::Cancelled`] is returned.
+    ///
+    /// If the data ends before all of the announced entries have been parsed (e.g. for a truncated file), [`Error::EntryCountMismatch`] is returned.
+    pub fn from_bytes_with_progress<F: FnMut(usize, usize) -> ControlFlow<()>>(
+        data: &[u8],
+        progress: F,
+    ) -> Result<BeatmapListing, Error> {
+        Self::parse(data, progress).map(|(listing, _)| listing)
+    }
+
+    /// Parses the contents of an `osu.db` file, also returning any data following the listing.
+    ///
+    /// Useful when the listing is embedded in a larger file, since [`BeatmapListing::from_bytes`] discards the remaining data.
+    pub fn from_bytes_with_remainder(data: &[u8]) -> Result<(BeatmapListing, &[u8]), Error> {
+        Self::parse(data, |_, _| ControlFlow::Continue(()))
+    }
+
+    fn parse<F: FnMut(usize, usize) -> ControlFlow<()>>(
+        data: &[u8],
+        progress: F,
+    ) -> Result<(BeatmapListing, &[u8]), Error> {
+        check_version(data, 0)?;
+
+        match beatmap_listing(data, progress).map_err(|e| e.to_owned())? {
+            (rest, ControlFlow::Continue(listing)) => Ok((listing, rest)),
+            (_, ControlFlow::Break(e)) => Err(e),
+        }
     }
 
     /// Convenience method for reading the contents of an `osu.db` file and parsing it as a `BeatmapListing`.
@@ -293,21 +432,622 @@ impl BeatmapListing {
         let data = std::fs::read(path)?;
         Self::from_bytes(&data)
     }
+
+    /// Finds the first beatmap entry with the specified MD5 hash in the contents of an `osu.db` file, without parsing the whole listing.
+    ///
+    /// Entries are parsed one at a time using [`BeatmapEntries`], and each non-matching entry is discarded once checked.
+    pub fn find_entry_by_md5(data: &[u8], md5: &str) -> Result<Option<BeatmapEntry>, Error> {
+        for entry in BeatmapEntries::from_bytes(data)? {
+            let entry = entry?;
+
+            if entry.md5.as_deref() == Some(md5) {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Interprets the osu! version of this listing as a date. See [`version_to_date`].
+    pub fn version_date(&self) -> Option<Date> {
+        version_to_date(self.version)
+    }
+
+    /// Encodes this listing in the `osu.db` format.
+    ///
+    /// Optional fields (e.g. the entry size and star ratings) are written when present, so a parsed listing is written back in the same layout.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.approximate_serialized_size());
+        write_beatmap_listing(&mut output, self);
+        output
+    }
+
+    /// Calculates the number of bytes [`BeatmapListing::to_bytes`] would produce, without encoding the listing.
+    ///
+    /// Useful for pre-allocating buffers or reporting progress when writing large listings.
+    pub fn approximate_serialized_size(&self) -> usize {
+        let header_size = 4 + 4 + 1 + 8 + osu_string_size(&self.player_name) + 4;
+        let entries_size: usize = self
+            .beatmaps
+            .iter()
+            .map(|entry| entry.serialized_size(self.version))
+            .sum();
+
+        header_size + entries_size + 4
+    }
+
+    /// Creates a human-readable, multi-line summary of this listing.
+    ///
+    /// This includes the version, player name and folder count, along with the number of beatmaps for each gameplay mode and ranked status.
+    pub fn summary_report(&self) -> String {
+        use std::fmt::Write;
+
+        let mut report = String::new();
+
+        match self.version_date() {
+            Some(date) => writeln!(report, "osu! version: {} ({})", self.version, date),
+            None => writeln!(report, "osu! version: {}", self.version),
+        }
+        .unwrap();
+
+        writeln!(
+            report,
+            "Player name: {}",
+            self.player_name.as_deref().unwrap_or_default()
+        )
+        .unwrap();
+        writeln!(report, "Folder count: {}", self.folder_count).unwrap();
+        writeln!(report, "Beatmap count: {}", self.beatmaps.len()).unwrap();
+
+        writeln!(report, "\nBeatmaps by gameplay mode:").unwrap();
+        for mode in GameplayMode::all() {
+            let count = self
+                .beatmaps
+                .iter()
+                .filter(|b| b.gameplay_mode == mode)
+                .count();
+
+            writeln!(report, "  {}: {}", mode, count).unwrap();
+        }
+
+        writeln!(report, "\nBeatmaps by ranked status:").unwrap();
+        for status in RankedStatus::all() {
+            let count = self
+                .beatmaps
+                .iter()
+                .filter(|b| b.ranked_status == status)
+                .count();
+
+            writeln!(report, "  {}: {}", status, count).unwrap();
+        }
+
+        report
+    }
+
+    /// Counts the beatmaps in this listing for each ranked status.
+    ///
+    /// Statuses which no beatmap has are omitted from the map.
+    pub fn ranked_status_counts(&self) -> HashMap<RankedStatus, usize> {
+        let mut counts = HashMap::new();
+
+        for beatmap in &self.beatmaps {
+            *counts.entry(beatmap.ranked_status).or_default() += 1;
+        }
+
+        counts
+    }
+
+    /// Finds the beatmaps in this listing which don't belong to any of the specified collections, in listing order.
+    ///
+    /// Beatmaps without an MD5 hash can't be referenced by a collection, so they're always included.
+    pub fn uncollected<'a>(&'a self, collections: &CollectionListing) -> Vec<&'a BeatmapEntry> {
+        let collected = collections
+            .collections
+            .iter()
+            .flat_map(|collection| collection.valid_md5s())
+            .collect::<HashSet<_>>();
+
+        self.beatmaps
+            .iter()
+            .filter(|b| b.md5.as_deref().is_none_or(|md5| !collected.contains(md5)))
+            .collect()
+    }
+
+    /// Lists the distinct song sources in this listing, in order of first appearance.
+    ///
+    /// Missing and empty sources are skipped.
+    pub fn distinct_sources(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+
+        self.beatmaps
+            .iter()
+            .filter_map(|b| b.song_source.as_deref())
+            .filter(|source| !source.is_empty() && seen.insert(*source))
+            .collect()
+    }
+
+    /// Lists the distinct song tags in this listing, in order of first appearance.
+    ///
+    /// Tags are separated by whitespace in each beatmap's `song_tags`, and are compared case-sensitively.
+    pub fn distinct_tags(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+
+        self.beatmaps
+            .iter()
+            .filter_map(|b| b.song_tags.as_deref())
+            .flat_map(str::split_whitespace)
+            .filter(|tag| seen.insert(*tag))
+            .collect()
+    }
+
+    /// Finds the beatmaps matching a search query, in listing order.
+    ///
+    /// A beatmap matches if the query is a substring of its artist, title, creator or tags (including the Unicode variants).
+    /// Matching is case-insensitive, and runs of whitespace are treated as a single space.
+    /// Note that this is a plain substring match - results aren't ranked, and typos aren't tolerated.
+    pub fn find(&self, query: &str) -> Vec<&BeatmapEntry> {
+        let query = normalize_search_text(query);
+
+        self.beatmaps
+            .iter()
+            .filter(|beatmap| {
+                beatmap
+                    .search_fields()
+                    .into_iter()
+                    .flatten()
+                    .any(|field| normalize_search_text(field).contains(&query))
+            })
+            .collect()
+    }
+
+    /// Finds the beatmaps matching a search query, sorted by relevance (most relevant first).
+    ///
+    /// Beatmaps are matched in the same way as [`BeatmapListing::find`], and each match is given a relevance score:
+    ///
+    /// - `1.0` => the title is exactly the query
+    /// - `0.75` => the title starts with the query
+    /// - `0.5` => the query is a substring of the title, artist or creator
+    /// - `0.25` => the query is only found in the tags
+    ///
+    /// Beatmaps with the same score are kept in listing order.
+    pub fn search_ranked(&self, query: &str) -> Vec<(&BeatmapEntry, f32)> {
+        let query = normalize_search_text(query);
+
+        let mut results = self
+            .beatmaps
+            .iter()
+            .filter_map(|beatmap| beatmap.search_score(&query).map(|score| (beatmap, score)))
+            .collect::<Vec<_>>();
+
+        results.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        results
+    }
+
+    /// Memory-maps an `osu.db` file, so that its beatmap entries can be parsed without reading the whole file into memory.
+    ///
+    /// # Safety
+    ///
+    /// Memory-mapping is only sound while the file isn't modified, e.g. by osu! itself.
+    /// If the file changes underneath the mapping, the parsed contents are unreliable and reading them is undefined behaviour.
+    /// Avoid using this while osu! is running.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> Result<MmapBeatmapListing, Error> {
+        let file = std::fs::File::open(path)?;
+        let mmap = memmap2::Mmap::map(&file)?;
+        Ok(MmapBeatmapListing { mmap })
+    }
+}
+
+impl<'a> BeatmapEntries<'a> {
+    /// Parses the header of an `osu.db` file, returning an iterator over its beatmap entries.
+    pub fn from_bytes(data: &'a [u8]) -> Result<BeatmapEntries<'a>, Error> {
+        check_version(data, 0)?;
+
+        let (input, ((version, ..), remaining)) =
+            tuple((listing_header, le_u32))(data).map_err(|e| e.to_owned())?;
+
+        Ok(BeatmapEntries {
+            input,
+            version,
+            parsed: 0,
+            remaining: remaining as usize,
+        })
+    }
+
+    /// The version of the `osu.db` file these entries belong to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+impl Iterator for BeatmapEntries<'_> {
+    type Item = Result<BeatmapEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let result = match beatmap_entry(self.version)(self.input) {
+            Ok((i, entry)) => {
+                self.input = i;
+                self.parsed += 1;
+                self.remaining -= 1;
+                return Some(Ok(entry));
+            }
+            Err(e) if is_end_of_data(&e) => Error::EntryCountMismatch {
+                expected: self.parsed + self.remaining,
+                found: self.parsed,
+            },
+            Err(e) => e.to_owned().into(),
+        };
+
+        self.remaining = 0;
+        Some(Err(result))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MmapBeatmapListing {
+    /// Returns an iterator which lazily parses the beatmap entries in the mapped file.
+    pub fn entries(&self) -> Result<BeatmapEntries<'_>, Error> {
+        BeatmapEntries::from_bytes(&self.mmap)
+    }
 }
 
-/// Parses an `osu.db` file.
-fn beatmap_listing(input: &[u8]) -> IResult<&[u8], BeatmapListing> {
-    let (i, version) = le_u32(input)?;
-    let (i, folder_count) = le_u32(i)?;
-    let (i, account_unlocked) = boolean(i)?;
-    let (i, account_unlock_date) = windows_datetime(i)?;
-    let (i, player_name) = osu_string(i)?;
-    let (i, beatmaps) = length_count(le_u32, beatmap_entry(version))(i)?;
+impl BeatmapEntry {
+    /// The number of hitcircles, sliders and spinners in this beatmap, in that order.
+    pub fn object_counts(&self) -> (u16, u16, u16) {
+        (self.hitcircle_count, self.slider_count, self.spinner_count)
+    }
+
+    /// The two fields which appear to hold modification times, in that order:
+    ///
+    /// - `last_modification_time`, which is the last modification time of the `.osu` file
+    /// - `unknown_u32`, near the end of the entry, which is suspected to also be a modification time
+    ///
+    /// The meaning of the second field hasn't been confirmed, so it's returned as-is rather than converted to a date.
+    pub fn modification_times(&self) -> (OffsetDateTime, u32) {
+        (self.last_modification_time, self.unknown_u32)
+    }
+
+    /// The difficulty ID, or `None` if it's unset (stored as `0xFFFFFFFF`, i.e. -1).
+    pub fn difficulty_id_opt(&self) -> Option<u32> {
+        (self.difficulty_id != u32::MAX).then_some(self.difficulty_id)
+    }
+
+    /// The beatmap ID, or `None` if it's unset (stored as `0xFFFFFFFF`, i.e. -1).
+    pub fn beatmap_id_opt(&self) -> Option<u32> {
+        (self.beatmap_id != u32::MAX).then_some(self.beatmap_id)
+    }
+
+    /// The forum thread ID, or `None` if it's unset (stored as `0xFFFFFFFF`, i.e. -1).
+    pub fn thread_id_opt(&self) -> Option<u32> {
+        (self.thread_id != u32::MAX).then_some(self.thread_id)
+    }
+
+    /// Whether this entry refers to an audio file, i.e. its audio filename is present and not blank.
+    ///
+    /// Corrupt entries may be missing their audio filename. This doesn't check whether the file actually exists.
+    pub fn has_audio(&self) -> bool {
+        self.audio_filename
+            .as_deref()
+            .is_some_and(|filename| !filename.trim().is_empty())
+    }
+
+    /// The per-beatmap osu!mania scroll speed, or `None` if the global scroll speed is used (stored as 0).
+    pub fn mania_scroll_speed_override(&self) -> Option<u8> {
+        (self.mania_scroll_speed != 0).then_some(self.mania_scroll_speed)
+    }
+
+    /// Bundles the osz2 flag and the per-beatmap skin, hitsound, storyboard and video settings.
+    pub fn settings(&self) -> BeatmapSettings {
+        BeatmapSettings {
+            is_osz2: self.is_osz2,
+            ignore_beatmap_hitsounds: self.ignore_beatmap_hitsounds,
+            ignore_beatmap_skin: self.ignore_beatmap_skin,
+            disable_storyboard: self.disable_storyboard,
+            disable_video: self.disable_video,
+            visual_override: self.visual_override,
+        }
+    }
+
+    /// The total number of hit objects in this beatmap.
+    ///
+    /// Note that this is not the max combo, since sliders can contribute more than one to combo (e.g. through ticks and repeats).
+    pub fn total_objects(&self) -> u32 {
+        self.hitcircle_count as u32 + self.slider_count as u32 + self.spinner_count as u32
+    }
+
+    /// A rough estimate of the max combo for this beatmap, counting hitcircles and spinners as 1 and sliders as 2.
+    ///
+    /// This is only an approximation: a slider's actual combo depends on its ticks and repeats, which aren't stored in `osu.db`.
+    /// So the estimate is usually too low for maps with long sliders, and too high for maps without slider ticks.
+    pub fn estimated_max_combo(&self) -> u32 {
+        self.hitcircle_count as u32 + self.slider_count as u32 * 2 + self.spinner_count as u32
+    }
+
+    /// Gets the star ratings calculated for a particular gameplay mode, if any.
+    pub fn star_ratings(&self, mode: GameplayMode) -> Option<&[StarRating]> {
+        match mode {
+            GameplayMode::Standard => self.star_ratings_std.as_deref(),
+            GameplayMode::Taiko => self.star_ratings_taiko.as_deref(),
+            GameplayMode::Catch => self.star_ratings_ctb.as_deref(),
+            GameplayMode::Mania => self.star_ratings_mania.as_deref(),
+        }
+    }
+
+    /// Looks up the stored star rating for a gameplay mode under a set of mods.
+    ///
+    /// The mods are first masked to the ones which affect difficulty (see [`Mods::difficulty_affecting`]).
+    /// If there is no rating for that combination, this falls back to the NoMod rating, since osu! only stores ratings for some combinations.
+    /// Returns `None` if there are no star ratings for the gameplay mode at all.
+    pub fn star_rating_with_mods(&self, mode: GameplayMode, mods: FlagSet<Mods>) -> Option<f64> {
+        let ratings = self.star_ratings(mode)?;
+        let mods = Mods::difficulty_affecting(mods);
+        let find = |mods: FlagSet<Mods>| ratings.iter().find(|r| r.mods == mods);
+
+        find(mods).or_else(|| find(Mods::none())).map(|r| r.rating)
+    }
+
+    /// Gets the gameplay modes this beatmap can be played in, i.e. its own mode plus any modes it has been converted to.
+    ///
+    /// Only osu! standard beatmaps can be converted, and a converted mode is only included if it has star ratings.
+    /// The beatmap's own mode is always included, even if its star ratings haven't been calculated.
+    pub fn available_modes(&self) -> Vec<GameplayMode> {
+        GameplayMode::all()
+            .into_iter()
+            .filter(|&mode| {
+                mode == self.gameplay_mode
+                    || (self.is_converted_playable_as(mode)
+                        && self.star_ratings(mode).is_some_and(|r| !r.is_empty()))
+            })
+            .collect()
+    }
+
+    /// Whether this beatmap can be played in the specified gameplay mode, either natively or as a converted beatmap.
+    ///
+    /// osu! standard beatmaps can be converted to any other mode, while taiko, catch and mania beatmaps can only be played in their own mode.
+    pub fn is_converted_playable_as(&self, mode: GameplayMode) -> bool {
+        self.gameplay_mode == mode || self.gameplay_mode == GameplayMode::Standard
+    }
+
+    /// Clones this beatmap entry, then applies some edits to the clone.
+    ///
+    /// This is more concise than struct update syntax when only a few fields need to change, e.g. before serializing the entry again.
+    pub fn with(&self, f: impl FnOnce(&mut BeatmapEntry)) -> BeatmapEntry {
+        let mut entry = self.clone();
+        f(&mut entry);
+        entry
+    }
+
+    /// Updates the grade achieved in each gameplay mode to the best grade out of the specified scores for this beatmap.
+    ///
+    /// Grades are recomputed from each score (see [`ScoreReplay::grade`]), so this can be used after editing a score's mods.
+    /// The grade for a gameplay mode is left unchanged if there are no scores for it.
+    pub fn update_grade_from_scores(&mut self, scores: &[ScoreReplay]) {
+        for mode in GameplayMode::all() {
+            let best = scores
+                .iter()
+                .filter(|s| s.gameplay_mode == mode)
+                .map(|s| s.grade())
+                .min_by_key(|g| g.rank());
+
+            if let Some(best) = best {
+                match mode {
+                    GameplayMode::Standard => self.grade_std = best,
+                    GameplayMode::Taiko => self.grade_taiko = best,
+                    GameplayMode::Catch => self.grade_catch = best,
+                    GameplayMode::Mania => self.grade_mania = best,
+                }
+            }
+        }
+    }
+
+    /// Calculates the number of bytes this entry occupies when written for the specified version.
+    fn serialized_size(&self, version: u32) -> usize {
+        let difficulty_size = if version < 20140609 { 1 } else { 4 };
+        let star_ratings_size = [
+            &self.star_ratings_std,
+            &self.star_ratings_taiko,
+            &self.star_ratings_ctb,
+            &self.star_ratings_mania,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|ratings| 4 + ratings.len() * 14)
+        .sum::<usize>();
+
+        let strings_size = [
+            &self.artist_name,
+            &self.artist_name_unicode,
+            &self.song_title,
+            &self.song_title_unicode,
+            &self.creator_name,
+            &self.difficulty,
+            &self.audio_filename,
+            &self.md5,
+            &self.beatmap_filename,
+            &self.song_source,
+            &self.song_tags,
+            &self.font,
+            &self.folder_name,
+        ]
+        .into_iter()
+        .map(osu_string_size)
+        .sum::<usize>();
+
+        self.size.map_or(0, |_| 4)
+            + strings_size
+            + 1 // Ranked status
+            + 3 * 2 // Object counts
+            + 8 // Last modification time
+            + 4 * difficulty_size
+            + 8 // Slider velocity
+            + star_ratings_size
+            + 3 * 4 // Drain time, total time, audio preview time
+            + 4 + self.timing_points.len() * 17
+            + 3 * 4 // Difficulty ID, beatmap ID, thread ID
+            + 4 // Grades
+            + 2 // Local offset
+            + 4 // Stack leniency
+            + 1 // Gameplay mode
+            + 2 // Online offset
+            + 1 // Is unplayed
+            + 8 // Last played
+            + 1 // Is osz2
+            + 8 // Last checked online
+            + 5 // Hitsound/skin/storyboard/video/visual overrides
+            + self.unknown_u16.map_or(0, |_| 2)
+            + 4 // Unknown u32
+            + 1 // Mania scroll speed
+    }
+
+    /// The text fields which are matched against when searching, i.e. the artist, title, creator and tags.
+    fn search_fields(&self) -> [Option<&str>; 6] {
+        [
+            self.artist_name.as_deref(),
+            self.artist_name_unicode.as_deref(),
+            self.song_title.as_deref(),
+            self.song_title_unicode.as_deref(),
+            self.creator_name.as_deref(),
+            self.song_tags.as_deref(),
+        ]
+    }
+
+    /// Scores how relevant this beatmap is to an already normalized search query. See [`BeatmapListing::search_ranked`].
+    fn search_score(&self, query: &str) -> Option<f32> {
+        let normalized = |field: &OsuString| field.as_deref().map(normalize_search_text);
+        let titles = [
+            normalized(&self.song_title),
+            normalized(&self.song_title_unicode),
+        ];
+        let others = [
+            normalized(&self.artist_name),
+            normalized(&self.artist_name_unicode),
+            normalized(&self.creator_name),
+        ];
+
+        if titles.iter().flatten().any(|title| *title == query) {
+            Some(1.0)
+        } else if titles
+            .iter()
+            .flatten()
+            .any(|title| title.starts_with(query))
+        {
+            Some(0.75)
+        } else if titles
+            .iter()
+            .chain(&others)
+            .flatten()
+            .any(|field| field.contains(query))
+        {
+            Some(0.5)
+        } else if normalized(&self.song_tags).is_some_and(|tags| tags.contains(query)) {
+            Some(0.25)
+        } else {
+            None
+        }
+    }
+
+    /// Creates the standard "Artist - Title [Difficulty]" label for this beatmap, with any missing fields left empty.
+    ///
+    /// If `prefer_unicode` is set, the Unicode artist name and song title are used when present (i.e. non-empty),
+    /// falling back to the regular fields otherwise.
+    pub fn display_name_unicode(&self, prefer_unicode: bool) -> String {
+        let pick = |unicode: &OsuString, fallback: &OsuString| {
+            unicode
+                .as_deref()
+                .filter(|s| prefer_unicode && !s.is_empty())
+                .or(fallback.as_deref())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        format!(
+            "{} - {} [{}]",
+            pick(&self.artist_name_unicode, &self.artist_name),
+            pick(&self.song_title_unicode, &self.song_title),
+            self.difficulty.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Builds the URL for this beatmap's difficulty on the osu! website.
+    ///
+    /// Returns `None` for beatmaps which weren't submitted, i.e. those whose beatmap or difficulty ID is unset or 0.
+    pub fn online_url(&self) -> Option<String> {
+        let beatmap_id = self.beatmap_id_opt().filter(|&id| id != 0)?;
+        let difficulty_id = self.difficulty_id_opt().filter(|&id| id != 0)?;
+
+        let mode = match self.gameplay_mode {
+            GameplayMode::Standard => "osu",
+            GameplayMode::Taiko => "taiko",
+            GameplayMode::Catch => "fruits",
+            GameplayMode::Mania => "mania",
+        };
+
+        Some(format!(
+            "https://osu.ppy.sh/beatmapsets/{}#{}/{}",
+            beatmap_id, mode, difficulty_id
+        ))
+    }
+}
+
+/// Normalizes text for searching, by lowercasing it and collapsing any runs of whitespace into a single space.
+fn normalize_search_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Parses an `osu.db` file, invoking the progress callback after each beatmap entry.
+///
+/// Stops early with [`ControlFlow::Break`] if the callback requests it, or if the data ends before all beatmap entries have been parsed.
+fn beatmap_listing<F: FnMut(usize, usize) -> ControlFlow<()>>(
+    input: &[u8],
+    mut progress: F,
+) -> IResult<&[u8], ControlFlow<Error, BeatmapListing>> {
+    let (i, (version, folder_count, account_unlocked, account_unlock_date, player_name)) =
+        listing_header(input)?;
+
+    // NOTE: Parsed manually rather than with length_count, so that progress can be reported between entries
+    let (mut i, beatmap_count) = le_u32(i)?;
+    let beatmap_count = beatmap_count as usize;
+    let parse_beatmap_entry = beatmap_entry(version);
+    let mut beatmaps = Vec::new();
+
+    while beatmaps.len() < beatmap_count {
+        let (remaining, beatmap) = match parse_beatmap_entry(i) {
+            Ok(result) => result,
+            Err(e) if is_end_of_data(&e) => {
+                let error = Error::EntryCountMismatch {
+                    expected: beatmap_count,
+                    found: beatmaps.len(),
+                };
+
+                return Ok((i, ControlFlow::Break(error)));
+            }
+            Err(e) => return Err(e),
+        };
+
+        i = remaining;
+        beatmaps.push(beatmap);
+
+        if progress(beatmaps.len(), beatmap_count).is_break() {
+            return Ok((i, ControlFlow::Break(Error::Cancelled)));
+        }
+    }
+
     let (i, user_permissions) = user_permissions(i)?;
 
     Ok((
         i,
-        BeatmapListing {
+        ControlFlow::Continue(BeatmapListing {
             version,
             folder_count,
             account_unlocked,
@@ -315,10 +1055,25 @@ fn beatmap_listing(input: &[u8]) -> IResult<&[u8], BeatmapListing> {
             player_name,
             beatmaps,
             user_permissions,
-        },
+        }),
     ))
 }
 
+/// Determines whether a parsing error was caused by running out of data, e.g. for a truncated file.
+///
+/// Parsers don't agree on how they report this (e.g. a tag reports [`ErrorKind::Tag`](nom::error::ErrorKind::Tag) for empty input),
+/// so this also checks whether the input had run out, rather than relying only on the error kind.
+fn is_end_of_data(error: &nom::Err<nom::error::Error<&[u8]>>) -> bool {
+    matches!(error, nom::Err::Error(e) if e.input.is_empty() || e.code == nom::error::ErrorKind::Eof)
+}
+
+/// Parses the fields preceding the beatmap entries in an `osu.db` file.
+pub(crate) fn listing_header(
+    input: &[u8],
+) -> IResult<&[u8], (u32, u32, bool, OffsetDateTime, OsuString)> {
+    tuple((le_u32, le_u32, boolean, windows_datetime, osu_string))(input)
+}
+
 /// Parses a beatmap entry in an `osu.db` file.
 fn beatmap_entry(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], BeatmapEntry> {
     let parse_difficulty: fn(&[u8]) -> IResult<&[u8], f32> = if version < 20140609 {
@@ -366,13 +1121,13 @@ fn beatmap_entry(version: u32) -> impl Fn(&[u8]) -> IResult<&[u8], BeatmapEntry>
         let (i, grade_taiko) = grade(i)?;
         let (i, grade_catch) = grade(i)?;
         let (i, grade_mania) = grade(i)?;
-        let (i, local_offset) = le_u16(i)?;
+        let (i, local_offset) = le_i16(i)?;
         let (i, stack_leniency) = le_f32(i)?;
         let (i, gameplay_mode) = gameplay_mode(i)?;
         let (i, song_source) = osu_string(i)?;
         let (i, song_tags) = osu_string(i)?;
 
-        let (i, online_offset) = le_u16(i)?;
+        let (i, online_offset) = le_i16(i)?;
         let (i, font) = osu_string(i)?;
         let (i, is_unplayed) = boolean(i)?;
         let (i, last_played) = windows_datetime(i)?;
@@ -543,10 +1298,142 @@ fn user_permissions(input: &[u8]) -> IResult<&[u8], FlagSet<UserPermissions>> {
     map(le_u32, FlagSet::<UserPermissions>::new_truncated)(input)
 }
 
+fn write_beatmap_listing(output: &mut Vec<u8>, listing: &BeatmapListing) {
+    output.extend_from_slice(&listing.version.to_le_bytes());
+    output.extend_from_slice(&listing.folder_count.to_le_bytes());
+    write_boolean(output, listing.account_unlocked);
+    write_windows_datetime(output, listing.account_unlock_date);
+    write_osu_string(output, &listing.player_name);
+    output.extend_from_slice(&(listing.beatmaps.len() as u32).to_le_bytes());
+
+    for entry in &listing.beatmaps {
+        write_beatmap_entry(output, listing.version, entry);
+    }
+
+    output.extend_from_slice(&listing.user_permissions.bits().to_le_bytes());
+}
+
+fn write_beatmap_entry(output: &mut Vec<u8>, version: u32, entry: &BeatmapEntry) {
+    fn write_difficulty(output: &mut Vec<u8>, version: u32, value: f32) {
+        if version < 20140609 {
+            output.push(value as u8);
+        } else {
+            output.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    fn write_star_ratings(output: &mut Vec<u8>, ratings: &Option<Vec<StarRating>>) {
+        if let Some(ratings) = ratings {
+            output.extend_from_slice(&(ratings.len() as u32).to_le_bytes());
+
+            for StarRating { mods, rating } in ratings {
+                output.push(0x08);
+                output.extend_from_slice(&mods.bits().to_le_bytes());
+                output.push(0x0d);
+                output.extend_from_slice(&rating.to_le_bytes());
+            }
+        }
+    }
+
+    if let Some(size) = entry.size {
+        output.extend_from_slice(&size.to_le_bytes());
+    }
+
+    write_osu_string(output, &entry.artist_name);
+    write_osu_string(output, &entry.artist_name_unicode);
+    write_osu_string(output, &entry.song_title);
+    write_osu_string(output, &entry.song_title_unicode);
+    write_osu_string(output, &entry.creator_name);
+    write_osu_string(output, &entry.difficulty);
+    write_osu_string(output, &entry.audio_filename);
+    write_osu_string(output, &entry.md5);
+    write_osu_string(output, &entry.beatmap_filename);
+
+    output.push(entry.ranked_status as u8);
+    output.extend_from_slice(&entry.hitcircle_count.to_le_bytes());
+    output.extend_from_slice(&entry.slider_count.to_le_bytes());
+    output.extend_from_slice(&entry.spinner_count.to_le_bytes());
+    write_windows_datetime(output, entry.last_modification_time);
+    write_difficulty(output, version, entry.approach_rate);
+    write_difficulty(output, version, entry.circle_size);
+    write_difficulty(output, version, entry.hp_drain);
+    write_difficulty(output, version, entry.overall_difficulty);
+    output.extend_from_slice(&entry.slider_velocity.to_le_bytes());
+
+    write_star_ratings(output, &entry.star_ratings_std);
+    write_star_ratings(output, &entry.star_ratings_taiko);
+    write_star_ratings(output, &entry.star_ratings_ctb);
+    write_star_ratings(output, &entry.star_ratings_mania);
+    output.extend_from_slice(&entry.drain_time.to_le_bytes());
+    output.extend_from_slice(&entry.total_time.to_le_bytes());
+    output.extend_from_slice(&entry.audio_preview_time.to_le_bytes());
+    output.extend_from_slice(&(entry.timing_points.len() as u32).to_le_bytes());
+
+    for timing_point in &entry.timing_points {
+        output.extend_from_slice(&timing_point.bpm.to_le_bytes());
+        output.extend_from_slice(&timing_point.song_offset.to_le_bytes());
+        write_boolean(output, timing_point.inherited);
+    }
+
+    output.extend_from_slice(&entry.difficulty_id.to_le_bytes());
+    output.extend_from_slice(&entry.beatmap_id.to_le_bytes());
+    output.extend_from_slice(&entry.thread_id.to_le_bytes());
+    output.push(entry.grade_std as u8);
+    output.push(entry.grade_taiko as u8);
+    output.push(entry.grade_catch as u8);
+    output.push(entry.grade_mania as u8);
+    output.extend_from_slice(&entry.local_offset.to_le_bytes());
+    output.extend_from_slice(&entry.stack_leniency.to_le_bytes());
+    output.push(entry.gameplay_mode as u8);
+    write_osu_string(output, &entry.song_source);
+    write_osu_string(output, &entry.song_tags);
+
+    output.extend_from_slice(&entry.online_offset.to_le_bytes());
+    write_osu_string(output, &entry.font);
+    write_boolean(output, entry.is_unplayed);
+    write_windows_datetime(output, entry.last_played);
+    write_boolean(output, entry.is_osz2);
+    write_osu_string(output, &entry.folder_name);
+    write_windows_datetime(output, entry.last_checked_online);
+    write_boolean(output, entry.ignore_beatmap_hitsounds);
+    write_boolean(output, entry.ignore_beatmap_skin);
+    write_boolean(output, entry.disable_storyboard);
+    write_boolean(output, entry.disable_video);
+    write_boolean(output, entry.visual_override);
+
+    if let Some(unknown_u16) = entry.unknown_u16 {
+        output.extend_from_slice(&unknown_u16.to_le_bytes());
+    }
+
+    output.extend_from_slice(&entry.unknown_u32.to_le_bytes());
+    output.push(entry.mania_scroll_speed);
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
 
+    #[test]
+    fn ranked_status_ordering_is_correct() {
+        use RankedStatus::*;
+
+        assert!(Ranked > Pending);
+        assert!(Loved > Ranked);
+        assert!(Unsubmitted < Pending);
+
+        let mut statuses = [
+            Loved,
+            Unknown,
+            Ranked,
+            Qualified,
+            Pending,
+            Approved,
+            Unsubmitted,
+        ];
+        statuses.sort();
+        assert_eq!(statuses, RankedStatus::all());
+    }
+
     #[test]
     fn ranked_status_decoding_works() {
         use RankedStatus::*;
@@ -568,6 +1455,30 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn beatmap_listing_header_is_little_endian() {
+        #[rustfmt::skip]
+        let input = [
+            0x82, 0x17, 0x34, 0x01,                         // version: 20191106
+            0x02, 0x01, 0x00, 0x00,                         // folder count: 258
+            0x01,                                           // account unlocked
+            0x00, 0x0E, 0x76, 0x8D, 0x7F, 0x8F, 0xDB, 0x08, // unlock date
+            0x00,                                           // player name
+        ];
+
+        let (_, (version, folder_count, account_unlocked, account_unlock_date, player_name)) =
+            listing_header(&input).unwrap();
+
+        assert_eq!(version, 20191106);
+        assert_eq!(folder_count, 258);
+        assert!(account_unlocked);
+        assert_eq!(
+            account_unlock_date,
+            time::macros::datetime!(2023-07-28 15:30:20 UTC)
+        );
+        assert_eq!(player_name, None);
+    }
+
     #[test]
     fn int_double_pair_decoding_works() {
         let int: u32 = 100;
@@ -590,7 +1501,7 @@ pub mod tests {
         missing_middle_tag.extend_from_slice(&int.to_le_bytes());
         missing_middle_tag.extend_from_slice(&double.to_le_bytes());
 
-        assert_eq!(int_double_pair(&pair), Ok((&extra[..], ((int, double)))));
+        assert_eq!(int_double_pair(&pair), Ok((&extra[..], (int, double))));
 
         assert_eq!(
             int_double_pair(&missing_front_tag),
@@ -609,6 +1520,36 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn user_permissions_decoding_works() {
+        use UserPermissions::*;
+
+        assert_eq!(
+            user_permissions(&0u32.to_le_bytes()),
+            Ok((&[][..], FlagSet::default()))
+        );
+        assert_eq!(
+            user_permissions(&5u32.to_le_bytes()),
+            Ok((&[][..], Normal | Supporter))
+        );
+        assert_eq!(
+            user_permissions(&42u32.to_le_bytes()),
+            Ok((&[][..], Moderator | Friend | WorldCupStaff))
+        );
+
+        // Unknown bits are ignored
+        assert_eq!(
+            user_permissions(&0x101u32.to_le_bytes()),
+            Ok((&[][..], FlagSet::from(Normal)))
+        );
+
+        let rendered = (Normal | Supporter | WorldCupStaff)
+            .into_iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(rendered, vec!["Normal", "Supporter", "World Cup Staff"]);
+    }
+
     #[test]
     fn timing_point_decoding_works() {
         let bpm: f64 = 180.0;
@@ -636,6 +1577,926 @@ pub mod tests {
         );
     }
 
+    /// Encodes a beatmap entry in the `osu.db` format for the specified version.
+    fn encode_beatmap_entry(version: u32, entry: &BeatmapEntry) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_beatmap_entry(&mut bytes, version, entry);
+        bytes
+    }
+
+    /// Creates a sample beatmap entry with the optional fields populated for the specified version.
+    pub(crate) fn sample_beatmap_entry(version: u32) -> BeatmapEntry {
+        let is_legacy = version < 20140609;
+        let star_ratings = |rating: f64| {
+            (!is_legacy).then(|| {
+                vec![StarRating {
+                    mods: Mods::none(),
+                    rating,
+                }]
+            })
+        };
+
+        BeatmapEntry {
+            size: (version < 20191106).then_some(512),
+            artist_name: Some("Artist".to_string()),
+            artist_name_unicode: Some("アーティスト".to_string()),
+            song_title: Some("Title".to_string()),
+            song_title_unicode: None,
+            creator_name: Some("Creator".to_string()),
+            difficulty: Some("Insane".to_string()),
+            audio_filename: Some("audio.mp3".to_string()),
+            md5: Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+            beatmap_filename: Some("Artist - Title (Creator) [Insane].osu".to_string()),
+            ranked_status: RankedStatus::Ranked,
+            hitcircle_count: 400,
+            slider_count: 200,
+            spinner_count: 3,
+            last_modification_time: time::macros::datetime!(2023-07-28 15:30:20 UTC),
+            approach_rate: 9.0,
+            circle_size: 4.0,
+            hp_drain: 6.0,
+            overall_difficulty: 8.0,
+            slider_velocity: 1.8,
+            star_ratings_std: star_ratings(5.25),
+            star_ratings_taiko: star_ratings(3.5),
+            star_ratings_ctb: star_ratings(4.75),
+            star_ratings_mania: star_ratings(2.25),
+            drain_time: 180,
+            total_time: 200_000,
+            audio_preview_time: 60_000,
+            timing_points: vec![TimingPoint {
+                bpm: 333.33,
+                song_offset: 1000.0,
+                inherited: false,
+            }],
+            difficulty_id: 12345,
+            beatmap_id: 678,
+            thread_id: 0,
+            grade_std: Grade::A,
+            grade_taiko: Grade::Unplayed,
+            grade_catch: Grade::Unplayed,
+            grade_mania: Grade::Unplayed,
+            local_offset: 5,
+            stack_leniency: 0.7,
+            gameplay_mode: GameplayMode::Standard,
+            song_source: Some("Source".to_string()),
+            song_tags: Some("tag1 tag2".to_string()),
+            online_offset: 10,
+            font: Some(String::new()),
+            is_unplayed: false,
+            last_played: time::macros::datetime!(2023-08-01 12:00:00 UTC),
+            is_osz2: false,
+            folder_name: Some("678 Artist - Title".to_string()),
+            last_checked_online: time::macros::datetime!(2023-08-02 12:00:00 UTC),
+            ignore_beatmap_hitsounds: true,
+            ignore_beatmap_skin: false,
+            disable_storyboard: true,
+            disable_video: false,
+            visual_override: true,
+            unknown_u16: is_legacy.then_some(0xBEEF),
+            unknown_u32: 0xDEADBEEF,
+            mania_scroll_speed: 20,
+        }
+    }
+
+    /// Encodes an `osu.db` file containing the specified beatmap entries.
+    pub(crate) fn encode_beatmap_listing(version: u32, entries: &[BeatmapEntry]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        bytes.push(0x01);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+        bytes.push(0x00);
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+
+        for entry in entries {
+            bytes.extend(encode_beatmap_entry(version, entry));
+        }
+
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn beatmap_listing_reports_progress() {
+        let version = 20191106;
+        let entries = vec![sample_beatmap_entry(version); 5];
+        let input = encode_beatmap_listing(version, &entries);
+
+        let mut reported = Vec::new();
+        let listing = BeatmapListing::from_bytes_with_progress(&input, |parsed, total| {
+            reported.push((parsed, total));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+        assert_eq!(listing.beatmaps, entries);
+        assert_eq!(reported, vec![(1, 5), (2, 5), (3, 5), (4, 5), (5, 5)]);
+
+        // No callbacks should fire for an empty listing
+        let input = encode_beatmap_listing(version, &[]);
+        let mut calls = 0;
+        BeatmapListing::from_bytes_with_progress(&input, |_, _| {
+            calls += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn beatmap_listing_reports_truncated_entries() {
+        let version = 20191106;
+        let entry = sample_beatmap_entry(version);
+        let entry_len = encode_beatmap_entry(version, &entry).len();
+
+        // Header, 3 entries, then user permissions
+        let input = encode_beatmap_listing(version, &vec![entry; 3]);
+        let header_len = input.len() - 3 * entry_len - 4;
+
+        // Ends cleanly after 2 entries, and partway through the 3rd entry
+        for end in [header_len + 2 * entry_len, header_len + 2 * entry_len + 10] {
+            let truncated = &input[..end];
+
+            assert!(matches!(
+                BeatmapListing::from_bytes(truncated),
+                Err(Error::EntryCountMismatch {
+                    expected: 3,
+                    found: 2
+                })
+            ));
+
+            let entries = BeatmapEntries::from_bytes(truncated)
+                .unwrap()
+                .collect::<Vec<_>>();
+
+            assert_eq!(entries.len(), 3);
+            assert!(matches!(
+                entries[2],
+                Err(Error::EntryCountMismatch {
+                    expected: 3,
+                    found: 2
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn beatmap_listing_truncated_at_star_rating_tag_is_reported() {
+        let version = 20191106;
+        let entry = sample_beatmap_entry(version);
+        let entry_len = encode_beatmap_entry(version, &entry).len();
+
+        let input = encode_beatmap_listing(version, &vec![entry; 3]);
+        let third_entry_start = input.len() - entry_len - 4;
+
+        // Cut the 3rd entry right before the tag of its first star rating pair
+        let mut pair = vec![0x08];
+        pair.extend_from_slice(&0u32.to_le_bytes());
+        pair.push(0x0d);
+        pair.extend_from_slice(&5.25f64.to_le_bytes());
+
+        let end = third_entry_start
+            + input[third_entry_start..]
+                .windows(pair.len())
+                .position(|window| window == pair)
+                .unwrap();
+
+        assert!(matches!(
+            BeatmapListing::from_bytes(&input[..end]),
+            Err(Error::EntryCountMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn beatmap_listing_can_be_cancelled() {
+        let version = 20191106;
+        let input = encode_beatmap_listing(version, &vec![sample_beatmap_entry(version); 5]);
+
+        // Cancel after 3 entries; no further entries should be parsed
+        let mut parsed_entries = 0;
+        let result = BeatmapListing::from_bytes_with_progress(&input, |parsed, _| {
+            parsed_entries = parsed;
+
+            if parsed == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert_eq!(parsed_entries, 3);
+    }
+
+    #[test]
+    fn beatmap_entries_are_parsed_lazily() {
+        let version = 20191106;
+        let entries = vec![sample_beatmap_entry(version); 3];
+        let input = encode_beatmap_listing(version, &entries);
+
+        let iter = BeatmapEntries::from_bytes(&input).unwrap();
+        assert_eq!(iter.version(), version);
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>().unwrap(), entries);
+
+        // Iteration should stop after the first error
+        let mut iter = BeatmapEntries::from_bytes(&input[..input.len() - 100]).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_beatmap_listing_works() {
+        let version = 20191106;
+        let entries = vec![sample_beatmap_entry(version); 3];
+        let path = std::env::temp_dir().join(format!("osu-db-parser-{}.db", std::process::id()));
+        std::fs::write(&path, encode_beatmap_listing(version, &entries)).unwrap();
+
+        let listing = unsafe { BeatmapListing::open_mmap(&path) }.unwrap();
+        let parsed = listing
+            .entries()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        drop(listing);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parsed, entries);
+    }
+
+    #[test]
+    fn negative_offsets_are_decoded() {
+        let version = 20191106;
+        let entry = sample_beatmap_entry(version).with(|e| {
+            e.local_offset = -10;
+            e.online_offset = -250;
+        });
+
+        // -10 = 0xFFF6, -250 = 0xFF06
+        let input = encode_beatmap_entry(version, &entry);
+        assert!(input.windows(2).any(|w| w == [0xF6, 0xFF]));
+        assert!(input.windows(2).any(|w| w == [0x06, 0xFF]));
+
+        let (_, decoded) = beatmap_entry(version)(&input).unwrap();
+        assert_eq!(decoded.local_offset, -10);
+        assert_eq!(decoded.online_offset, -250);
+    }
+
+    #[test]
+    fn beatmap_entry_round_trip_preserves_unknown_fields() {
+        for version in [20140608, 20150203, 20191106] {
+            let entry = sample_beatmap_entry(version);
+            let input = encode_beatmap_entry(version, &entry);
+
+            let (rest, decoded) = beatmap_entry(version)(&input).unwrap();
+            assert!(rest.is_empty(), "version {} left trailing bytes", version);
+            assert_eq!(decoded, entry, "version {} did not round trip", version);
+            assert_eq!(encode_beatmap_entry(version, &decoded), input);
+        }
+
+        // The unknown short is only present in legacy versions
+        let (_, legacy) = beatmap_entry(20140608)(&encode_beatmap_entry(
+            20140608,
+            &sample_beatmap_entry(20140608),
+        ))
+        .unwrap();
+        assert_eq!(legacy.unknown_u16, Some(0xBEEF));
+        assert_eq!(legacy.unknown_u32, 0xDEADBEEF);
+
+        let (_, modern) = beatmap_entry(20191106)(&encode_beatmap_entry(
+            20191106,
+            &sample_beatmap_entry(20191106),
+        ))
+        .unwrap();
+        assert_eq!(modern.unknown_u16, None);
+        assert_eq!(modern.unknown_u32, 0xDEADBEEF);
+    }
+
+    #[test]
+    fn beatmap_entry_modification_times_are_correct() {
+        let (_, entry) = beatmap_entry(20191106)(&encode_beatmap_entry(
+            20191106,
+            &sample_beatmap_entry(20191106),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            entry.modification_times(),
+            (time::macros::datetime!(2023-07-28 15:30:20 UTC), 0xDEADBEEF)
+        );
+    }
+
+    #[test]
+    fn beatmap_entry_id_sentinels_are_unset() {
+        let mut entry = sample_beatmap_entry(20191106);
+        entry.difficulty_id = 129891;
+        entry.beatmap_id = 39804;
+        entry.thread_id = 0;
+
+        assert_eq!(entry.difficulty_id_opt(), Some(129891));
+        assert_eq!(entry.beatmap_id_opt(), Some(39804));
+        assert_eq!(entry.thread_id_opt(), Some(0));
+
+        entry.difficulty_id = 0xFFFFFFFF;
+        entry.beatmap_id = 0xFFFFFFFF;
+        entry.thread_id = 0xFFFFFFFF;
+
+        assert_eq!(entry.difficulty_id_opt(), None);
+        assert_eq!(entry.beatmap_id_opt(), None);
+        assert_eq!(entry.thread_id_opt(), None);
+    }
+
+    #[test]
+    fn beatmap_entry_has_audio_works() {
+        let entry = sample_beatmap_entry(20191106);
+        assert!(entry.has_audio());
+
+        for audio_filename in [None, Some(String::new()), Some("  ".to_string())] {
+            let entry = entry.with(|e| e.audio_filename = audio_filename);
+            assert!(!entry.has_audio());
+        }
+    }
+
+    #[test]
+    fn beatmap_entry_mania_scroll_speed_override_works() {
+        let entry = sample_beatmap_entry(20191106);
+        assert_eq!(entry.mania_scroll_speed_override(), Some(20));
+
+        let entry = entry.with(|e| e.mania_scroll_speed = 0);
+        assert_eq!(entry.mania_scroll_speed_override(), None);
+    }
+
+    #[test]
+    fn beatmap_entry_settings_works() {
+        let entry = sample_beatmap_entry(20191106);
+
+        assert_eq!(
+            entry.settings(),
+            BeatmapSettings {
+                is_osz2: false,
+                ignore_beatmap_hitsounds: true,
+                ignore_beatmap_skin: false,
+                disable_storyboard: true,
+                disable_video: false,
+                visual_override: true,
+            }
+        );
+
+        let entry = entry.with(|e| {
+            e.is_osz2 = true;
+            e.disable_video = true;
+        });
+        assert!(entry.settings().is_osz2);
+        assert!(entry.settings().disable_video);
+    }
+
+    #[test]
+    fn beatmap_listing_remainder_is_returned() {
+        let version = 20191106;
+        let mut input = encode_beatmap_listing(version, &[sample_beatmap_entry(version)]);
+        let listing_len = input.len();
+        input.extend_from_slice(&[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let (listing, rest) = BeatmapListing::from_bytes_with_remainder(&input).unwrap();
+        assert_eq!(listing.beatmaps.len(), 1);
+        assert_eq!(rest, &[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let (_, rest) = BeatmapListing::from_bytes_with_remainder(&input[..listing_len]).unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn find_entry_by_md5_works() {
+        let version = 20191106;
+        let entries = ["first", "second", "third"]
+            .map(|md5| sample_beatmap_entry(version).with(|e| e.md5 = Some(md5.to_string())));
+        let input = encode_beatmap_listing(version, &entries);
+
+        assert_eq!(
+            BeatmapListing::find_entry_by_md5(&input, "second").unwrap(),
+            Some(entries[1].clone())
+        );
+        assert_eq!(
+            BeatmapListing::find_entry_by_md5(&input, "unknown").unwrap(),
+            None
+        );
+
+        // Entries after the match aren't parsed
+        let truncated = &input[..input.len() - 10];
+        assert!(BeatmapListing::find_entry_by_md5(truncated, "second")
+            .unwrap()
+            .is_some());
+        assert!(matches!(
+            BeatmapListing::find_entry_by_md5(truncated, "unknown"),
+            Err(Error::EntryCountMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn approximate_serialized_size_matches_to_bytes() {
+        for version in [20140608, 20140609, 20191106] {
+            let mut long_tags = sample_beatmap_entry(version);
+            long_tags.song_tags = Some("tag ".repeat(100));
+            long_tags.timing_points.clear();
+
+            let listing = BeatmapListing::from_bytes(&encode_beatmap_listing(
+                version,
+                &[sample_beatmap_entry(version), long_tags],
+            ))
+            .unwrap();
+
+            let bytes = listing.to_bytes();
+            assert_eq!(listing.approximate_serialized_size(), bytes.len());
+            assert_eq!(
+                BeatmapListing::from_bytes(&bytes).unwrap().beatmaps,
+                listing.beatmaps
+            );
+        }
+    }
+
+    #[test]
+    fn beatmap_entry_trailing_fields_layout_is_correct() {
+        // Legacy layout: disable video, visual override, unknown short, unknown int, mania scroll speed
+        let version = 20140608;
+        let mut input = encode_beatmap_entry(version, &sample_beatmap_entry(version));
+        input.truncate(input.len() - 9);
+        input.extend_from_slice(&[0x00, 0x01, 0x34, 0x12, 0x78, 0x56, 0x34, 0x12, 0x0F]);
+
+        let (rest, entry) = beatmap_entry(version)(&input).unwrap();
+        assert!(rest.is_empty());
+        assert!(!entry.disable_video);
+        assert!(entry.visual_override);
+        assert_eq!(entry.unknown_u16, Some(0x1234));
+        assert_eq!(entry.unknown_u32, 0x12345678);
+        assert_eq!(entry.mania_scroll_speed, 0x0F);
+
+        // Current layout: the unknown short is no longer present
+        let version = 20191106;
+        let mut input = encode_beatmap_entry(version, &sample_beatmap_entry(version));
+        input.truncate(input.len() - 7);
+        input.extend_from_slice(&[0x01, 0x00, 0x78, 0x56, 0x34, 0x12, 0x0F]);
+
+        let (rest, entry) = beatmap_entry(version)(&input).unwrap();
+        assert!(rest.is_empty());
+        assert!(entry.disable_video);
+        assert!(!entry.visual_override);
+        assert_eq!(entry.unknown_u16, None);
+        assert_eq!(entry.unknown_u32, 0x12345678);
+        assert_eq!(entry.mania_scroll_speed, 0x0F);
+    }
+
+    #[test]
+    fn legacy_difficulty_bytes_are_widened() {
+        let version = 20140608;
+        let sample = sample_beatmap_entry(version).with(|e| {
+            e.artist_name = None;
+            e.artist_name_unicode = None;
+            e.song_title = None;
+            e.song_title_unicode = None;
+            e.creator_name = None;
+            e.difficulty = None;
+            e.audio_filename = None;
+            e.md5 = None;
+            e.beatmap_filename = None;
+        });
+
+        // Size, 9 empty strings, ranked status, object counts and last modification time come first
+        let mut input = encode_beatmap_entry(version, &sample);
+        let offset = 4 + 9 + 1 + 3 * 2 + 8;
+        assert_eq!(input[offset..offset + 4], [9, 4, 6, 8]);
+        assert_eq!(
+            input[offset + 4..offset + 12],
+            sample.slider_velocity.to_le_bytes()
+        );
+
+        input[offset..offset + 4].copy_from_slice(&[10, 7, 3, 0]);
+
+        let (rest, entry) = beatmap_entry(version)(&input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(entry.approach_rate, 10.0);
+        assert_eq!(entry.circle_size, 7.0);
+        assert_eq!(entry.hp_drain, 3.0);
+        assert_eq!(entry.overall_difficulty, 0.0);
+        assert_eq!(entry.slider_velocity, sample.slider_velocity);
+
+        // Older versions also have an extra u16 before the trailing u32 and scroll speed
+        assert_eq!(entry.unknown_u16, Some(0xBEEF));
+        assert_eq!(
+            input[input.len() - 7..input.len() - 5],
+            0xBEEFu16.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn star_ratings_absent_before_20140609() {
+        // Star ratings aren't stored at all in older versions
+        let version = 20140608;
+        let input = encode_beatmap_entry(version, &sample_beatmap_entry(version));
+        let (rest, entry) = beatmap_entry(version)(&input).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(entry.star_ratings_std, None);
+        assert_eq!(entry.star_ratings_taiko, None);
+        assert_eq!(entry.star_ratings_ctb, None);
+        assert_eq!(entry.star_ratings_mania, None);
+
+        // Newer versions always store the section, even if it's empty
+        let version = 20140609;
+        let mut sample = sample_beatmap_entry(version);
+        sample.star_ratings_taiko = Some(Vec::new());
+
+        let input = encode_beatmap_entry(version, &sample);
+        let (_, entry) = beatmap_entry(version)(&input).unwrap();
+
+        assert!(entry.star_ratings_std.is_some_and(|r| r.len() == 1));
+        assert_eq!(entry.star_ratings_taiko, Some(Vec::new()));
+    }
+
+    #[test]
+    fn beatmap_entry_display_is_correct() {
+        let mut entry = sample_beatmap_entry(20191106);
+        assert_eq!(entry.to_string(), "Artist - Title [Insane]");
+
+        entry.artist_name = None;
+        entry.difficulty = Some(String::new());
+        assert_eq!(entry.to_string(), " - Title []");
+    }
+
+    #[test]
+    fn beatmap_entry_display_name_unicode_is_correct() {
+        // Sample has a Unicode artist name, but no Unicode song title
+        let mut entry = sample_beatmap_entry(20191106);
+        assert_eq!(
+            entry.display_name_unicode(true),
+            "アーティスト - Title [Insane]"
+        );
+        assert_eq!(entry.display_name_unicode(false), "Artist - Title [Insane]");
+
+        entry.song_title_unicode = Some("タイトル".to_string());
+        assert_eq!(
+            entry.display_name_unicode(true),
+            "アーティスト - タイトル [Insane]"
+        );
+        assert_eq!(entry.display_name_unicode(false), "Artist - Title [Insane]");
+
+        // Empty Unicode fields should fall back as well
+        entry.artist_name_unicode = Some(String::new());
+        entry.song_title_unicode = None;
+        assert_eq!(entry.display_name_unicode(true), "Artist - Title [Insane]");
+    }
+
+    #[test]
+    fn uncollected_beatmaps_are_found() {
+        use crate::collections::Collection;
+
+        let version = 20191106;
+        let listing = BeatmapListing::from_bytes(&encode_beatmap_listing(
+            version,
+            &["collected", "uncollected"]
+                .map(|md5| sample_beatmap_entry(version).with(|e| e.md5 = Some(md5.to_string()))),
+        ))
+        .unwrap();
+
+        let collections = CollectionListing {
+            version,
+            collections: vec![Collection {
+                name: Some("Favourites".to_string()),
+                beatmap_md5s: vec![Some("collected".to_string()), Some("missing".to_string())],
+            }],
+        };
+
+        assert_eq!(
+            listing.uncollected(&collections),
+            vec![&listing.beatmaps[1]]
+        );
+    }
+
+    #[test]
+    fn ranked_status_counts_are_correct() {
+        let version = 20191106;
+        let listing = BeatmapListing::from_bytes(&encode_beatmap_listing(
+            version,
+            &[
+                RankedStatus::Ranked,
+                RankedStatus::Loved,
+                RankedStatus::Ranked,
+            ]
+            .map(|status| sample_beatmap_entry(version).with(|e| e.ranked_status = status)),
+        ))
+        .unwrap();
+
+        let counts = listing.ranked_status_counts();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&RankedStatus::Ranked], 2);
+        assert_eq!(counts[&RankedStatus::Loved], 1);
+        assert_eq!(counts.get(&RankedStatus::Pending), None);
+    }
+
+    #[test]
+    fn distinct_sources_and_tags_work() {
+        let version = 20191106;
+        let entry = |source: Option<&str>, tags: Option<&str>| {
+            sample_beatmap_entry(version).with(|e| {
+                e.song_source = source.map(str::to_string);
+                e.song_tags = tags.map(str::to_string);
+            })
+        };
+
+        let listing = BeatmapListing::from_bytes(&encode_beatmap_listing(
+            version,
+            &[
+                entry(Some("Touhou"), Some("zun  shrine maiden")),
+                entry(None, Some("shrine touhou\tzun")),
+                entry(Some(""), None),
+                entry(Some("Vocaloid"), Some("")),
+                entry(Some("Touhou"), Some("maiden")),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(listing.distinct_sources(), vec!["Touhou", "Vocaloid"]);
+        assert_eq!(
+            listing.distinct_tags(),
+            vec!["zun", "shrine", "maiden", "touhou"]
+        );
+    }
+
+    #[test]
+    fn beatmap_listing_summary_report_is_correct() {
+        let version = 20191106;
+        let mut entries = vec![sample_beatmap_entry(version); 3];
+        entries[1].gameplay_mode = GameplayMode::Taiko;
+        entries[2].ranked_status = RankedStatus::Loved;
+
+        let mut listing =
+            BeatmapListing::from_bytes(&encode_beatmap_listing(version, &entries)).unwrap();
+        listing.player_name = Some("peppy".to_string());
+
+        let report = listing.summary_report();
+        let lines = report.lines().collect::<Vec<_>>();
+
+        for expected in [
+            "osu! version: 20191106 (2019-11-06)",
+            "Player name: peppy",
+            "Folder count: 3",
+            "Beatmap count: 3",
+            "  Standard: 2",
+            "  Taiko: 1",
+            "  Mania: 0",
+            "  Ranked: 2",
+            "  Loved: 1",
+            "  Pending: 0",
+        ] {
+            assert!(lines.contains(&expected), "missing line: {expected}");
+        }
+    }
+
+    #[test]
+    fn beatmap_listing_find_works() {
+        let version = 20191106;
+        let mut entries = vec![sample_beatmap_entry(version); 3];
+
+        entries[0].artist_name = Some("xi".to_string());
+        entries[0].song_title = Some("FREEDOM DiVE".to_string());
+        entries[0].song_tags = Some("touhou  bullet hell".to_string());
+
+        entries[1].artist_name = Some("DragonForce".to_string());
+        entries[1].song_title = Some("Through the Fire and Flames".to_string());
+        entries[1].creator_name = Some("Ekoro".to_string());
+
+        entries[2].artist_name = Some("Camellia".to_string());
+        entries[2].song_title = Some("Exit This Earth's Atomosphere".to_string());
+        entries[2].song_title_unicode = Some("Exit This Earth's Atomosphere ＜Extra＞".to_string());
+
+        let listing =
+            BeatmapListing::from_bytes(&encode_beatmap_listing(version, &entries)).unwrap();
+        let titles = |query: &str| {
+            listing
+                .find(query)
+                .into_iter()
+                .map(|b| b.song_title.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+
+        // Case-insensitive, across the title, creator and tags
+        assert_eq!(titles("freedom dive"), vec!["FREEDOM DiVE"]);
+        assert_eq!(titles("EKORO"), vec!["Through the Fire and Flames"]);
+        assert_eq!(titles("bullet hell"), vec!["FREEDOM DiVE"]);
+
+        // Whitespace is normalized in both the query and the fields
+        assert_eq!(
+            titles("  through   the fire "),
+            vec!["Through the Fire and Flames"]
+        );
+        assert_eq!(titles("touhou bullet"), vec!["FREEDOM DiVE"]);
+
+        // Unicode fields are searched too
+        assert_eq!(titles("＜extra＞"), vec!["Exit This Earth's Atomosphere"]);
+
+        assert!(titles("not a beatmap").is_empty());
+    }
+
+    #[test]
+    fn beatmap_listing_search_ranked_works() {
+        let version = 20191106;
+        let mut entries = vec![sample_beatmap_entry(version); 4];
+
+        entries[0].song_title = Some("Tag Only".to_string());
+        entries[0].song_tags = Some("blue zenith".to_string());
+
+        entries[1].song_title = Some("Another Blue".to_string());
+
+        entries[2].song_title = Some("Blue Zenith".to_string());
+
+        entries[3].song_title = Some("Blue Zenith (Extended)".to_string());
+
+        let listing =
+            BeatmapListing::from_bytes(&encode_beatmap_listing(version, &entries)).unwrap();
+        let results = listing
+            .search_ranked("blue zenith")
+            .into_iter()
+            .map(|(b, score)| (b.song_title.as_deref().unwrap_or_default(), score))
+            .collect::<Vec<_>>();
+
+        // An exact title match outranks a prefix match, which outranks a tag-only match
+        assert_eq!(
+            results,
+            vec![
+                ("Blue Zenith", 1.0),
+                ("Blue Zenith (Extended)", 0.75),
+                ("Tag Only", 0.25),
+            ]
+        );
+
+        // Substring matches are kept in listing order
+        let results = listing
+            .search_ranked("blue")
+            .into_iter()
+            .map(|(b, score)| (b.song_title.as_deref().unwrap_or_default(), score))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            vec![
+                ("Blue Zenith", 0.75),
+                ("Blue Zenith (Extended)", 0.75),
+                ("Another Blue", 0.5),
+                ("Tag Only", 0.25),
+            ]
+        );
+    }
+
+    #[test]
+    fn beatmap_entry_grades_are_updated_from_scores() {
+        use crate::scores::tests::sample_score_replay;
+
+        let mut entry = sample_beatmap_entry(20191106);
+        entry.grade_std = Grade::Unplayed;
+        entry.grade_taiko = Grade::B;
+
+        // 96% 300s with no misses is an S, which becomes a silver S with Hidden
+        let mut score = sample_score_replay(GameplayMode::Standard);
+        score.hits_300 = 96;
+        score.hits_100 = 4;
+
+        let mut worse = score.clone();
+        worse.hits_300 = 85;
+        worse.hits_100 = 15;
+
+        entry.update_grade_from_scores(&[worse.clone(), score.clone()]);
+        assert_eq!(entry.grade_std, Grade::S);
+        assert_eq!(entry.grade_taiko, Grade::B);
+
+        score.mods = Mods::Hidden.into();
+        entry.update_grade_from_scores(&[worse, score]);
+        assert_eq!(entry.grade_std, Grade::SilverS);
+    }
+
+    #[test]
+    fn beatmap_entry_grade_update_prefers_silver_regardless_of_order() {
+        use crate::scores::tests::sample_score_replay;
+
+        let mut nomod = sample_score_replay(GameplayMode::Standard);
+        nomod.hits_300 = 100;
+
+        let mut hidden = nomod.clone();
+        hidden.mods = Mods::Hidden.into();
+
+        for scores in [[nomod.clone(), hidden.clone()], [hidden, nomod]] {
+            let mut entry = sample_beatmap_entry(20191106);
+            entry.update_grade_from_scores(&scores);
+            assert_eq!(entry.grade_std, Grade::SilverSS);
+        }
+    }
+
+    #[test]
+    fn beatmap_entry_with_applies_edits_to_clone() {
+        let entry = sample_beatmap_entry(20191106);
+        let edited = entry.with(|e| e.difficulty = Some("Extra".to_string()));
+
+        assert_eq!(edited.difficulty.as_deref(), Some("Extra"));
+        assert_ne!(entry.difficulty, edited.difficulty);
+
+        // Everything else is left as-is
+        assert_eq!(
+            BeatmapEntry {
+                difficulty: entry.difficulty.clone(),
+                ..edited
+            },
+            entry
+        );
+    }
+
+    #[test]
+    fn beatmap_entry_star_rating_with_mods_works() {
+        let entry = sample_beatmap_entry(20191106).with(|e| {
+            e.star_ratings_std = Some(vec![
+                StarRating {
+                    mods: Mods::none(),
+                    rating: 5.25,
+                },
+                StarRating {
+                    mods: Mods::DoubleTime.into(),
+                    rating: 7.5,
+                },
+            ]);
+            e.star_ratings_mania = None;
+        });
+
+        let rating = |mods| entry.star_rating_with_mods(GameplayMode::Standard, mods);
+        assert_eq!(rating(Mods::DoubleTime.into()), Some(7.5));
+        assert_eq!(rating(Mods::Nightcore | Mods::Hidden), Some(7.5));
+        assert_eq!(rating(Mods::Easy.into()), Some(5.25));
+        assert_eq!(rating(Mods::none()), Some(5.25));
+
+        assert_eq!(
+            entry.star_rating_with_mods(GameplayMode::Mania, Mods::none()),
+            None
+        );
+    }
+
+    #[test]
+    fn beatmap_entry_conversion_rules_are_correct() {
+        let standard = sample_beatmap_entry(20191106);
+
+        for mode in GameplayMode::all() {
+            assert!(standard.is_converted_playable_as(mode));
+        }
+
+        let mania = standard.with(|e| e.gameplay_mode = GameplayMode::Mania);
+        assert!(mania.is_converted_playable_as(GameplayMode::Mania));
+        assert!(!mania.is_converted_playable_as(GameplayMode::Standard));
+        assert!(!mania.is_converted_playable_as(GameplayMode::Taiko));
+        assert!(!mania.is_converted_playable_as(GameplayMode::Catch));
+    }
+
+    #[test]
+    fn beatmap_entry_available_modes_are_correct() {
+        use GameplayMode::*;
+
+        // Standard maps include converts with star ratings
+        let mut entry = sample_beatmap_entry(20191106);
+        assert_eq!(entry.available_modes(), vec![Standard, Taiko, Catch, Mania]);
+
+        entry.star_ratings_mania = Some(Vec::new());
+        assert_eq!(entry.available_modes(), vec![Standard, Taiko, Catch]);
+
+        // Star ratings are absent before 20140609, so only the native mode is available
+        let legacy = sample_beatmap_entry(20140608);
+        assert_eq!(legacy.available_modes(), vec![Standard]);
+
+        // Other modes can't be converted, even if star ratings are present
+        entry.gameplay_mode = Mania;
+        entry.star_ratings_mania = None;
+        assert_eq!(entry.available_modes(), vec![Mania]);
+        assert_eq!(entry.star_ratings(Mania), None);
+        assert_eq!(entry.star_ratings(Taiko).map(|r| r.len()), Some(1));
+    }
+
+    #[test]
+    fn beatmap_entry_object_counts_are_correct() {
+        let mut entry = sample_beatmap_entry(20191106);
+        assert_eq!(entry.object_counts(), (400, 200, 3));
+        assert_eq!(entry.total_objects(), 603);
+        assert_eq!(entry.estimated_max_combo(), 400 + 200 * 2 + 3);
+
+        // Shouldn't overflow when summing
+        entry.hitcircle_count = u16::MAX;
+        entry.slider_count = u16::MAX;
+        entry.spinner_count = u16::MAX;
+        assert_eq!(entry.total_objects(), 3 * u16::MAX as u32);
+        assert_eq!(entry.estimated_max_combo(), 4 * u16::MAX as u32);
+    }
+
     #[test]
     fn star_ratings_decoding_works() {
         let ratings = vec![
@@ -661,4 +2522,26 @@ pub mod tests {
 
         assert_eq!(star_ratings(&input), Ok((&[][..], ratings)));
     }
+
+    #[test]
+    fn star_ratings_mods_are_decoded() {
+        // HardRock + DoubleTime (0x50) at 6.5 stars
+        let mut input = 1u32.to_le_bytes().to_vec();
+        input.push(0x08);
+        input.extend_from_slice(&0x50u32.to_le_bytes());
+        input.push(0x0d);
+        input.extend_from_slice(&6.5f64.to_le_bytes());
+
+        let (_, ratings) = star_ratings(&input).unwrap();
+        assert_eq!(
+            ratings,
+            vec![StarRating {
+                mods: Mods::HardRock | Mods::DoubleTime,
+                rating: 6.5,
+            }]
+        );
+
+        // Empty list
+        assert_eq!(star_ratings(&0u32.to_le_bytes()), Ok((&[][..], Vec::new())));
+    }
 }
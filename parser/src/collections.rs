@@ -1,15 +1,23 @@
 //! Models for the `collection.db` database file, which contains information on beatmap collections.
 
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
-use nom::{multi::length_count, number::complete::le_u32, IResult};
+use nom::{
+    error::{ErrorKind, ParseError},
+    multi::count,
+    number::complete::le_u32,
+    IResult,
+};
+use time::Date;
 
 use crate::{
-    common::{osu_string, OsuString},
+    beatmaps::BeatmapEntry,
+    common::{check_version, osu_string, version_to_date, write_osu_string, OsuString},
     error::Error,
 };
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CollectionListing {
     /// Version (e.g. 20150203)
     pub version: u32,
@@ -19,6 +27,7 @@ pub struct CollectionListing {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Collection {
     /// Name of the collection
     pub name: OsuString,
@@ -29,7 +38,11 @@ pub struct Collection {
 
 impl CollectionListing {
     /// Parses the contents of a `collection.db` file.
+    ///
+    /// Collection and beatmap counts are checked against the remaining data before parsing, so a corrupt count fails early with [`Error::Parser`].
     pub fn from_bytes(data: &[u8]) -> Result<CollectionListing, Error> {
+        check_version(data, 0)?;
+
         let (_, listing) = collection_listing(data).map_err(|e| e.to_owned())?;
         Ok(listing)
     }
@@ -39,12 +52,101 @@ impl CollectionListing {
         let data = std::fs::read(path)?;
         Self::from_bytes(&data)
     }
+
+    /// Encodes this listing in the `collection.db` format.
+    ///
+    /// Missing and empty strings are written differently (see [`write_osu_string`]), so beatmap MD5 hashes are written back exactly as they were parsed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_collection_listing(&mut output, self);
+        output
+    }
+
+    /// Interprets the osu! version of this listing as a date. See [`version_to_date`].
+    pub fn version_date(&self) -> Option<Date> {
+        version_to_date(self.version)
+    }
+
+    /// Finds the collections which contain the beatmap with the specified MD5 hash, in listing order.
+    ///
+    /// This is the inverse of [`Collection::resolve`], i.e. it goes from a beatmap to its collections.
+    pub fn collections_containing<'a>(&'a self, md5: &str) -> Vec<&'a Collection> {
+        self.collections
+            .iter()
+            .filter(|collection| collection.valid_md5s().any(|m| m == md5))
+            .collect()
+    }
+
+    /// Appends a new, empty collection with the specified name, returning it so that beatmaps can be added.
+    ///
+    /// Note that osu! doesn't expect duplicate collection names, so callers should check for these beforehand.
+    pub fn add_collection(&mut self, name: impl Into<String>) -> &mut Collection {
+        self.collections.push(Collection {
+            name: Some(name.into()),
+            beatmap_md5s: Vec::new(),
+        });
+
+        self.collections.last_mut().unwrap()
+    }
+}
+
+impl Collection {
+    /// Iterates over the valid beatmap MD5 hashes in this collection, skipping any that are missing or empty.
+    pub fn valid_md5s(&self) -> impl Iterator<Item = &str> {
+        self.beatmap_md5s
+            .iter()
+            .filter_map(|md5| md5.as_deref())
+            .filter(|md5| !md5.is_empty())
+    }
+
+    /// The number of valid beatmap MD5 hashes in this collection.
+    pub fn len(&self) -> usize {
+        self.valid_md5s().count()
+    }
+
+    /// Whether this collection has no valid beatmap MD5 hashes.
+    pub fn is_empty(&self) -> bool {
+        self.valid_md5s().next().is_none()
+    }
+
+    /// Removes the beatmap with the specified MD5 hash from this collection, returning whether it was present.
+    ///
+    /// Any duplicate references to the beatmap are removed as well.
+    pub fn remove_beatmap(&mut self, md5: &str) -> bool {
+        let len = self.beatmap_md5s.len();
+        self.beatmap_md5s.retain(|m| m.as_deref() != Some(md5));
+        self.beatmap_md5s.len() != len
+    }
+
+    /// Lists the online URLs of the beatmaps in this collection, resolving each MD5 hash through the specified lookup.
+    ///
+    /// Beatmaps which can't be resolved (e.g. because they aren't installed) are skipped,
+    /// as are beatmaps which weren't submitted. See [`BeatmapEntry::online_url`].
+    pub fn to_url_list(&self, index: &HashMap<&str, &BeatmapEntry>) -> Vec<String> {
+        self.resolve(index)
+            .flatten()
+            .filter_map(BeatmapEntry::online_url)
+            .collect()
+    }
+
+    /// Resolves each beatmap in this collection to its entry in the specified MD5 lookup, in order.
+    ///
+    /// Yields `None` for any beatmap whose MD5 is missing or isn't present in the lookup (e.g. if the beatmap isn't installed).
+    pub fn resolve<'a>(
+        &'a self,
+        index: &'a HashMap<&str, &'a BeatmapEntry>,
+    ) -> impl Iterator<Item = Option<&'a BeatmapEntry>> {
+        self.beatmap_md5s
+            .iter()
+            .map(|md5| md5.as_deref().and_then(|md5| index.get(md5).copied()))
+    }
 }
 
 /// Parses a `collection.db` file.
-fn collection_listing(input: &[u8]) -> IResult<&[u8], CollectionListing> {
+pub(crate) fn collection_listing(input: &[u8]) -> IResult<&[u8], CollectionListing> {
     let (i, version) = le_u32(input)?;
-    let (i, collections) = length_count(le_u32, collection)(i)?;
+    let (i, collections_count) = bounded_count(MIN_COLLECTION_SIZE)(i)?;
+    let (i, collections) = count(collection, collections_count)(i)?;
 
     Ok((
         i,
@@ -58,7 +160,283 @@ fn collection_listing(input: &[u8]) -> IResult<&[u8], CollectionListing> {
 /// Parses a collection entry in the `collection.db` file.
 fn collection(input: &[u8]) -> IResult<&[u8], Collection> {
     let (i, name) = osu_string(input)?;
-    let (i, beatmap_md5s) = length_count(le_u32, osu_string)(i)?;
+    let (i, md5s_count) = bounded_count(MIN_MD5_SIZE)(i)?;
+    let (i, beatmap_md5s) = count(osu_string, md5s_count)(i)?;
 
     Ok((i, Collection { name, beatmap_md5s }))
 }
+
+fn write_collection_listing(output: &mut Vec<u8>, listing: &CollectionListing) {
+    output.extend_from_slice(&listing.version.to_le_bytes());
+    output.extend_from_slice(&(listing.collections.len() as u32).to_le_bytes());
+
+    for collection in &listing.collections {
+        write_osu_string(output, &collection.name);
+        output.extend_from_slice(&(collection.beatmap_md5s.len() as u32).to_le_bytes());
+
+        for md5 in &collection.beatmap_md5s {
+            write_osu_string(output, md5);
+        }
+    }
+}
+
+/// Minimum size of a collection entry, i.e. an empty name and a beatmap count of 0.
+pub(crate) const MIN_COLLECTION_SIZE: usize = 5;
+
+/// Minimum size of a beatmap MD5 hash, i.e. an empty string.
+pub(crate) const MIN_MD5_SIZE: usize = 1;
+
+/// Parses an entry count, failing if the remaining input is too short to hold that many entries of the specified minimum size.
+fn bounded_count(min_entry_size: usize) -> impl Fn(&[u8]) -> IResult<&[u8], usize> {
+    move |input| {
+        let (i, count) = le_u32(input)?;
+        let count = count as usize;
+
+        if count.saturating_mul(min_entry_size) > i.len() {
+            return Err(nom::Err::Failure(nom::error::Error::from_error_kind(
+                input,
+                ErrorKind::TooLarge,
+            )));
+        }
+
+        Ok((i, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{beatmaps::tests::sample_beatmap_entry, common::GameplayMode};
+
+    #[test]
+    fn collection_listing_header_is_little_endian() {
+        // 20191106 = 0x01341782, followed by a collection count of 0
+        let listing =
+            CollectionListing::from_bytes(&[0x82, 0x17, 0x34, 0x01, 0x00, 0x00, 0x00, 0x00])
+                .unwrap();
+
+        assert_eq!(listing.version, 20191106);
+        assert!(listing.collections.is_empty());
+    }
+
+    #[test]
+    fn collection_listing_round_trip_preserves_missing_md5s() {
+        // Version, one collection named "A", then a missing MD5 and an empty MD5
+        let mut data = 20191106u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0x0b, 0x01, b'A']);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[0x00, 0x0b, 0x00]);
+
+        let listing = CollectionListing::from_bytes(&data).unwrap();
+        assert_eq!(
+            listing.collections[0].beatmap_md5s,
+            vec![None, Some(String::new())]
+        );
+        assert_eq!(listing.to_bytes(), data);
+    }
+
+    #[test]
+    fn oversized_counts_are_rejected() {
+        // Version, then a collection count of 0xFFFFFFFF with only a few bytes remaining
+        let mut data = 20191106u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00]);
+
+        let error = CollectionListing::from_bytes(&data).unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::Parser(nom::Err::Failure(e)) if e.code == ErrorKind::TooLarge
+        ));
+        assert_eq!(error.offset(data.len()), Some(4));
+
+        // Version, one collection, then a beatmap count of 0xFFFFFFFF
+        let mut data = 20191106u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.push(0x00);
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.push(0x00);
+
+        let error = CollectionListing::from_bytes(&data).unwrap_err();
+        assert_eq!(error.offset(data.len()), Some(9));
+    }
+
+    #[test]
+    fn collection_valid_md5s_skips_missing_entries() {
+        let collection = Collection {
+            name: Some("Favourites".to_string()),
+            beatmap_md5s: vec![
+                Some("first".to_string()),
+                None,
+                Some(String::new()),
+                Some("second".to_string()),
+            ],
+        };
+
+        assert_eq!(
+            collection.valid_md5s().collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+        assert_eq!(collection.len(), 2);
+        assert!(!collection.is_empty());
+
+        let invalid = Collection {
+            name: None,
+            beatmap_md5s: vec![None, Some(String::new())],
+        };
+
+        assert_eq!(invalid.len(), 0);
+        assert!(invalid.is_empty());
+
+        let empty = Collection {
+            name: None,
+            beatmap_md5s: Vec::new(),
+        };
+
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn collection_resolve_works() {
+        let mut first = sample_beatmap_entry(20191106);
+        first.md5 = Some("first".to_string());
+
+        let mut second = sample_beatmap_entry(20191106);
+        second.md5 = Some("second".to_string());
+
+        let index = HashMap::from([("first", &first), ("second", &second)]);
+        let collection = Collection {
+            name: Some("Favourites".to_string()),
+            beatmap_md5s: vec![
+                Some("second".to_string()),
+                Some("unknown".to_string()),
+                None,
+                Some("first".to_string()),
+            ],
+        };
+
+        let resolved = collection.resolve(&index).collect::<Vec<_>>();
+        assert_eq!(resolved, vec![Some(&second), None, None, Some(&first)]);
+    }
+
+    #[test]
+    fn collection_url_list_skips_unresolved_md5s() {
+        let mut entry = sample_beatmap_entry(20191106);
+        entry.md5 = Some("installed".to_string());
+        entry.gameplay_mode = GameplayMode::Taiko;
+        entry.beatmap_id = 39804;
+        entry.difficulty_id = 129891;
+
+        let unsubmitted = |md5: &str, id: u32| {
+            sample_beatmap_entry(20191106).with(|e| {
+                e.md5 = Some(md5.to_string());
+                e.beatmap_id = id;
+                e.difficulty_id = id;
+            })
+        };
+        let zero_ids = unsubmitted("zero ids", 0);
+        let unset_ids = unsubmitted("unset ids", u32::MAX);
+
+        let index = HashMap::from([
+            ("installed", &entry),
+            ("zero ids", &zero_ids),
+            ("unset ids", &unset_ids),
+        ]);
+        let collection = Collection {
+            name: Some("Favourites".to_string()),
+            beatmap_md5s: vec![
+                Some("installed".to_string()),
+                Some("not installed".to_string()),
+                Some("zero ids".to_string()),
+                Some("unset ids".to_string()),
+            ],
+        };
+
+        assert_eq!(
+            collection.to_url_list(&index),
+            vec!["https://osu.ppy.sh/beatmapsets/39804#taiko/129891"]
+        );
+    }
+
+    #[test]
+    fn collections_containing_works() {
+        let collection = |name: &str, md5s: &[&str]| Collection {
+            name: Some(name.to_string()),
+            beatmap_md5s: md5s.iter().map(|md5| Some(md5.to_string())).collect(),
+        };
+
+        let listing = CollectionListing {
+            version: 20191106,
+            collections: vec![
+                collection("Favourites", &["first", "second"]),
+                collection("Tournament", &["third"]),
+                collection("Practice", &["second", "third"]),
+            ],
+        };
+
+        let names = |md5: &str| {
+            listing
+                .collections_containing(md5)
+                .into_iter()
+                .map(|c| c.name.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("second"), vec!["Favourites", "Practice"]);
+        assert_eq!(names("first"), vec!["Favourites"]);
+        assert!(names("unknown").is_empty());
+        assert!(names("").is_empty());
+    }
+
+    #[test]
+    fn remove_beatmap_removes_all_references() {
+        let mut collection = Collection {
+            name: Some("Favourites".to_string()),
+            beatmap_md5s: vec![
+                Some("first".to_string()),
+                None,
+                Some("second".to_string()),
+                Some("first".to_string()),
+            ],
+        };
+
+        assert!(collection.remove_beatmap("first"));
+        assert_eq!(
+            collection.beatmap_md5s,
+            vec![None, Some("second".to_string())]
+        );
+
+        assert!(!collection.remove_beatmap("first"));
+        assert!(!collection.remove_beatmap("unknown"));
+        assert_eq!(collection.beatmap_md5s.len(), 2);
+    }
+
+    #[test]
+    fn add_collection_appends_an_empty_collection() {
+        let mut listing = CollectionListing {
+            version: 20191106,
+            collections: Vec::new(),
+        };
+
+        listing.add_collection("Favourites");
+        listing
+            .add_collection("Practice")
+            .beatmap_md5s
+            .push(Some("md5".to_string()));
+
+        assert_eq!(listing.collections.len(), 2);
+        assert_eq!(listing.collections[0].name.as_deref(), Some("Favourites"));
+        assert!(listing.collections[0].is_empty());
+        assert_eq!(listing.collections[1].name.as_deref(), Some("Practice"));
+        assert_eq!(listing.collections[1].len(), 1);
+
+        // The new collections should survive a round trip
+        let parsed = CollectionListing::from_bytes(&listing.to_bytes()).unwrap();
+        assert_eq!(parsed.collections.len(), 2);
+        assert_eq!(
+            parsed.collections[1].beatmap_md5s,
+            vec![Some("md5".to_string())]
+        );
+    }
+}
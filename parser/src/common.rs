@@ -1,16 +1,25 @@
 use flagset::{flags, FlagSet};
 use nom::{
     bytes::complete::{take, take_while},
-    combinator::{fail, map, map_opt, map_res},
+    combinator::{fail, map, map_opt},
     number::complete::{le_u32, le_u64, u8},
     IResult,
 };
-use time::{macros::datetime, Duration, OffsetDateTime};
+use time::{macros::datetime, Date, Duration, Month, OffsetDateTime};
 
+use crate::error::Error;
+
+/// A string found in osu!'s database file formats.
+///
+/// `None` and `Some("")` are encoded differently (see [`osu_string`]), so they shouldn't be treated as equivalent when the data will be written back.
 pub type OsuString = Option<String>;
 
+/// The epoch used by .NET's `DateTime.Ticks`.
+const WINDOWS_EPOCH: OffsetDateTime = datetime!(0001-01-01 0:00 UTC);
+
 /// Represents the different gameplay modes for a beatmap.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameplayMode {
     Standard = 0,
     Taiko = 1,
@@ -20,6 +29,7 @@ pub enum GameplayMode {
 
 /// Represents a grade achieved on a beatmap.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Grade {
     SilverSS = 0,
     SilverS = 1,
@@ -101,11 +111,142 @@ impl std::fmt::Display for Grade {
     }
 }
 
+impl GameplayMode {
+    /// Returns all gameplay modes, in the order of their numeric values.
+    pub fn all() -> [GameplayMode; 4] {
+        use GameplayMode::*;
+
+        [Standard, Taiko, Catch, Mania]
+    }
+}
+
+impl Grade {
+    /// Ranks this grade for comparisons, where a lower value is a better grade.
+    ///
+    /// Silver grades rank just above their regular counterparts, so that picking the best grade doesn't depend on the order of the scores.
+    pub(crate) fn rank(self) -> u8 {
+        use Grade::*;
+
+        match self {
+            SilverSS => 0,
+            SS => 1,
+            SilverS => 2,
+            S => 3,
+            A => 4,
+            B => 5,
+            C => 6,
+            D => 7,
+            Unplayed => 8,
+        }
+    }
+}
+
 impl Mods {
     /// Creates a blank set of gameplay modifiers (i.e. NoMod).
     pub fn none() -> FlagSet<Mods> {
         FlagSet::<Mods>::new_truncated(0)
     }
+
+    /// Masks a set of gameplay modifiers to the ones which affect difficulty, i.e. the mods used as star rating keys.
+    ///
+    /// Nightcore is reduced to DoubleTime, since they share the same difficulty.
+    pub fn difficulty_affecting(mods: FlagSet<Mods>) -> FlagSet<Mods> {
+        mods & (Mods::Easy
+            | Mods::HardRock
+            | Mods::DoubleTime
+            | Mods::HalfTime
+            | Mods::Flashlight
+            | Mods::KeyMod
+            | Mods::Key1
+            | Mods::Key2
+            | Mods::Key3
+            | Mods::Key9
+            | Mods::Coop)
+    }
+
+    /// The short acronym shown for this mod in-game, e.g. `HD` for Hidden.
+    pub fn acronym(self) -> &'static str {
+        use Mods::*;
+
+        match self {
+            NoFail => "NF",
+            Easy => "EZ",
+            TouchDevice => "TD",
+            Hidden => "HD",
+            HardRock => "HR",
+            SuddenDeath => "SD",
+            DoubleTime => "DT",
+            Relax => "RX",
+            HalfTime => "HT",
+            Nightcore => "NC",
+            Flashlight => "FL",
+            Autoplay => "AT",
+            SpunOut => "SO",
+            Autopilot => "AP",
+            Perfect => "PF",
+            Key4 => "4K",
+            Key5 => "5K",
+            Key6 => "6K",
+            Key7 => "7K",
+            Key8 => "8K",
+            KeyMod => "KM",
+            FadeIn => "FI",
+            Random => "RD",
+            Cinema => "CN",
+            TargetPractice => "TP",
+            Key9 => "9K",
+            Coop => "CO",
+            Key1 => "1K",
+            Key3 => "3K",
+            Key2 => "2K",
+            ScoreV2 => "V2",
+            Mirror => "MR",
+        }
+    }
+
+    /// Renders a set of gameplay modifiers as concatenated acronyms (e.g. `HDDT`), or an empty string for NoMod.
+    ///
+    /// Aggregate flags are skipped, as in [`individual_mods`].
+    pub fn acronyms(mods: FlagSet<Mods>) -> String {
+        individual_mods(mods).map(Mods::acronym).collect()
+    }
+}
+
+/// Iterates over the individual mods in a set of gameplay modifiers.
+///
+/// Aggregate flags are never yielded: `KeyMod` is skipped in favour of the individual key mods,
+/// and `DoubleTime` is skipped when `Nightcore` is present (since Nightcore always includes it).
+pub fn individual_mods(mods: FlagSet<Mods>) -> impl Iterator<Item = Mods> {
+    mods.into_iter().filter(move |m| match m {
+        Mods::KeyMod => false,
+        Mods::DoubleTime => !mods.contains(Mods::Nightcore),
+        _ => true,
+    })
+}
+
+/// Interprets an osu! version number (e.g. `20231225`) as the date it represents.
+///
+/// Returns `None` if the version isn't a valid `YYYYMMDD` date.
+pub fn version_to_date(version: u32) -> Option<Date> {
+    let year = i32::try_from(version / 10000).ok()?;
+    let month = Month::try_from(u8::try_from(version / 100 % 100).ok()?).ok()?;
+    let day = u8::try_from(version % 100).ok()?;
+
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+/// Checks that the version number at `offset` is a valid date, to reject files which aren't legacy osu! database files early.
+///
+/// If there isn't enough data to read the version, this is left for the parser to report.
+pub(crate) fn check_version(data: &[u8], offset: usize) -> Result<(), Error> {
+    let version = data
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+
+    match version {
+        Some(version) if version_to_date(version).is_none() => Err(Error::UnrecognizedFormat),
+        _ => Ok(()),
+    }
 }
 
 /// Parses a boolean value in osu!'s database file formats.
@@ -140,10 +281,25 @@ pub fn modifiers(input: &[u8]) -> IResult<&[u8], FlagSet<Mods>> {
 }
 
 /// Decodes a ULEB128 value into an unsigned pointer-sized integer.
+///
+/// Fails with [`ErrorKind::TooLarge`](nom::error::ErrorKind::TooLarge) if the value doesn't fit in a `usize`, e.g. in corrupt data.
+/// This covers both values with too many bytes, and values whose final byte has bits beyond the range of a `usize`.
 pub fn uleb128(input: &[u8]) -> IResult<&[u8], usize> {
-    let (i, uleb_start) = take_while(|byte| byte & 0x80 > 1)(input)?;
+    let too_large = || {
+        nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TooLarge,
+        ))
+    };
+
+    let (i, uleb_start) = take_while(|byte| byte & 0x80 != 0)(input)?;
     let (i, uleb_final) = u8(i)?;
 
+    // Each byte holds 7 bits, so the final byte must start below usize::BITS
+    if uleb_start.len() >= usize::BITS.div_ceil(7) as usize {
+        return Err(too_large());
+    }
+
     let mut result = 0;
     let mut shift = 0;
 
@@ -152,7 +308,13 @@ pub fn uleb128(input: &[u8]) -> IResult<&[u8], usize> {
         shift += 7;
     }
 
-    result |= ((uleb_final & 0x7F) as usize) << shift;
+    // The final byte may still have bits which would be shifted out of range
+    let uleb_final = (uleb_final & 0x7F) as usize;
+    if (uleb_final << shift) >> shift != uleb_final {
+        return Err(too_large());
+    }
+
+    result |= uleb_final << shift;
     Ok((i, result))
 }
 
@@ -166,15 +328,26 @@ pub fn uleb128(input: &[u8]) -> IResult<&[u8], usize> {
 /// - `0x00` => Empty string marker; output is `None`
 /// - `0x0b, 0x00` => Zero length string; output is `Some("")`
 pub fn osu_string(input: &[u8]) -> IResult<&[u8], OsuString> {
+    osu_string_with_encoding(input, |bytes| {
+        std::str::from_utf8(bytes).ok().map(str::to_string)
+    })
+}
+
+/// Decodes a string found in osu!'s database file formats, using a custom decoder for the string bytes.
+///
+/// This has the same layout as [`osu_string`], but allows for strings which weren't stored as UTF-8 (e.g. in some very old files).
+/// If the decoder returns `None`, then parsing fails.
+pub fn osu_string_with_encoding(
+    input: &[u8],
+    decoder: fn(&[u8]) -> Option<String>,
+) -> IResult<&[u8], OsuString> {
     let (i, head) = u8(input)?;
 
     match head {
         0x00 => Ok((i, None)),
         0x0b => {
             let (i, length) = uleb128(i)?;
-            map(map_res(take(length), std::str::from_utf8), |s| {
-                Some(s.to_string())
-            })(i)
+            map(map_opt(take(length), decoder), Some)(i)
         }
         _ => fail(input),
     }
@@ -182,8 +355,6 @@ pub fn osu_string(input: &[u8]) -> IResult<&[u8], OsuString> {
 
 /// Parses a DateTime from .NET's [`DateTime.Ticks`](https://learn.microsoft.com/en-us/dotnet/api/system.datetime.ticks?view=netframework-4.7.2).
 pub fn windows_datetime(input: &[u8]) -> IResult<&[u8], OffsetDateTime> {
-    const WINDOWS_EPOCH: OffsetDateTime = datetime!(0001-01-01 0:00 UTC);
-
     map_opt(le_u64, |ticks| {
         // In .NET, there are 10,000 ticks per millisecond
         // So 10 ticks / microsecond, 0.01 ticks per nanosecond
@@ -193,10 +364,127 @@ pub fn windows_datetime(input: &[u8]) -> IResult<&[u8], OffsetDateTime> {
     })(input)
 }
 
+/// Encodes a boolean value in osu!'s database file formats.
+pub fn write_boolean(output: &mut Vec<u8>, value: bool) {
+    output.push(value as u8);
+}
+
+/// Encodes an unsigned pointer-sized integer as a ULEB128 value.
+pub fn write_uleb128(output: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            output.push(byte);
+            break;
+        }
+
+        output.push(byte | 0x80);
+    }
+}
+
+/// Encodes a string in osu!'s database file formats, preserving the distinction between `None` and `Some("")`.
+///
+/// See [`osu_string`] for more details.
+pub fn write_osu_string(output: &mut Vec<u8>, value: &OsuString) {
+    if let Some(value) = value {
+        output.push(0x0b);
+        write_uleb128(output, value.len());
+        output.extend_from_slice(value.as_bytes());
+    } else {
+        output.push(0x00);
+    }
+}
+
+/// Calculates the number of bytes [`write_osu_string`] produces for a string.
+pub(crate) fn osu_string_size(value: &OsuString) -> usize {
+    match value {
+        Some(value) => {
+            let length_size = (usize::BITS - value.len().leading_zeros())
+                .div_ceil(7)
+                .max(1);
+            1 + length_size as usize + value.len()
+        }
+        None => 1,
+    }
+}
+
+/// Encodes a DateTime as .NET's [`DateTime.Ticks`](https://learn.microsoft.com/en-us/dotnet/api/system.datetime.ticks?view=netframework-4.7.2).
+///
+/// Ticks can't be negative, so datetimes before 0001-01-01 are clamped to it.
+pub fn write_windows_datetime(output: &mut Vec<u8>, value: OffsetDateTime) {
+    let ticks = (value - WINDOWS_EPOCH).whole_nanoseconds() / 100;
+    output.extend_from_slice(&u64::try_from(ticks).unwrap_or(0).to_le_bytes());
+}
+
+/// Serializes an [`OffsetDateTime`] as an integer Unix timestamp (in seconds), rather than an RFC3339 string.
+///
+/// Select it for individual fields with `#[serde(with = "osu_db_parser::common::serde_unix_timestamp")]`.
+/// Any sub-second precision is discarded when serializing.
+#[cfg(feature = "serde")]
+pub mod serde_unix_timestamp {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use time::OffsetDateTime;
+
+    pub fn serialize<S: Serializer>(
+        value: &OffsetDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(value.unix_timestamp())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<OffsetDateTime, D::Error> {
+        let timestamp = i64::deserialize(deserializer)?;
+        OffsetDateTime::from_unix_timestamp(timestamp).map_err(de::Error::custom)
+    }
+}
+
+/// Conversions between the [`OffsetDateTime`] values used by this crate and [`chrono`]'s UTC datetimes.
+#[cfg(feature = "chrono")]
+pub mod chrono_interop {
+    use chrono::{DateTime, Utc};
+    use time::{Duration, OffsetDateTime};
+
+    /// Converts a datetime to a UTC `chrono` datetime, preserving the nanosecond precision.
+    pub fn to_chrono(value: OffsetDateTime) -> DateTime<Utc> {
+        // chrono supports a wider range of years than time, so this can't fail
+        DateTime::from_timestamp(value.unix_timestamp(), value.nanosecond())
+            .expect("datetime should be within chrono's supported range")
+    }
+
+    /// Converts a UTC `chrono` datetime back to an [`OffsetDateTime`].
+    ///
+    /// Returns `None` if the year is outside of the range supported by `time` (i.e. beyond ±9999).
+    pub fn from_chrono(value: DateTime<Utc>) -> Option<OffsetDateTime> {
+        let datetime = OffsetDateTime::from_unix_timestamp(value.timestamp()).ok()?;
+        datetime.checked_add(Duration::nanoseconds(value.timestamp_subsec_nanos().into()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn version_to_date_works() {
+        assert_eq!(
+            version_to_date(20231225),
+            Some(time::macros::date!(2023 - 12 - 25))
+        );
+        assert_eq!(
+            version_to_date(20150203),
+            Some(time::macros::date!(2015 - 02 - 03))
+        );
+
+        // Out of range months/days aren't valid dates
+        assert_eq!(version_to_date(20231301), None);
+        assert_eq!(version_to_date(20230230), None);
+        assert_eq!(version_to_date(0), None);
+    }
+
     #[test]
     fn boolean_decoding_works() {
         // Any non-zero byte should result in true
@@ -227,6 +515,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn gameplay_mode_all_is_ordered() {
+        use GameplayMode::*;
+
+        assert_eq!(GameplayMode::all(), [Standard, Taiko, Catch, Mania]);
+
+        for (i, mode) in GameplayMode::all().into_iter().enumerate() {
+            assert_eq!(mode as usize, i);
+        }
+    }
+
+    #[test]
+    fn individual_mods_excludes_aggregates() {
+        let mods = Mods::Key4 | Mods::Hidden;
+        assert_eq!(
+            individual_mods(mods).collect::<Vec<_>>(),
+            vec![Mods::Hidden, Mods::Key4]
+        );
+
+        let mods = Mods::Nightcore | Mods::HardRock;
+        assert_eq!(
+            individual_mods(mods).collect::<Vec<_>>(),
+            vec![Mods::HardRock, Mods::Nightcore]
+        );
+
+        let mods = Mods::DoubleTime.into();
+        assert_eq!(
+            individual_mods(mods).collect::<Vec<_>>(),
+            vec![Mods::DoubleTime]
+        );
+
+        // Every key mod is set, which also satisfies KeyMod
+        let mods = Mods::KeyMod.into();
+        assert_eq!(
+            individual_mods(mods).collect::<Vec<_>>(),
+            vec![Mods::Key4, Mods::Key5, Mods::Key6, Mods::Key7, Mods::Key8]
+        );
+
+        assert_eq!(individual_mods(Mods::none()).count(), 0);
+    }
+
+    #[test]
+    fn mods_acronyms_are_correct() {
+        assert_eq!(Mods::acronyms(Mods::Hidden | Mods::DoubleTime), "HDDT");
+        assert_eq!(Mods::acronyms(Mods::Nightcore | Mods::HardRock), "HRNC");
+        assert_eq!(Mods::acronyms(Mods::Key7 | Mods::Mirror), "7KMR");
+        assert_eq!(Mods::acronyms(Mods::none()), "");
+    }
+
+    #[test]
+    fn difficulty_affecting_mods_are_kept() {
+        assert_eq!(
+            Mods::difficulty_affecting(Mods::Hidden | Mods::DoubleTime),
+            Mods::DoubleTime
+        );
+        assert_eq!(
+            Mods::difficulty_affecting(Mods::Nightcore | Mods::HardRock | Mods::NoFail),
+            Mods::DoubleTime | Mods::HardRock
+        );
+        assert_eq!(
+            Mods::difficulty_affecting(Mods::Key4 | Mods::Mirror),
+            Mods::Key4
+        );
+        assert_eq!(
+            Mods::difficulty_affecting(Mods::Hidden | Mods::SuddenDeath),
+            Mods::none()
+        );
+    }
+
     #[test]
     fn uleb128_decoding_works() {
         // 0xE5, 0x8E, 0x26 ==> 624485
@@ -239,6 +596,24 @@ mod tests {
             Ok((&[0x80, 0x81, 0x82][..], 624485))
         );
 
+        // Too many continuation bytes to fit in a usize
+        let overlong = [0x80; 16];
+        assert_eq!(
+            uleb128(&[&overlong[..], &[0x01]].concat()).map_err(|e| e.map(|e| e.code)),
+            Err(nom::Err::Error(nom::error::ErrorKind::TooLarge))
+        );
+
+        // Final byte with bits beyond the range of a usize
+        let mut max = Vec::new();
+        write_uleb128(&mut max, usize::MAX);
+        assert_eq!(uleb128(&max), Ok((&[][..], usize::MAX)));
+
+        *max.last_mut().unwrap() = 0x7F;
+        assert_eq!(
+            uleb128(&max).map_err(|e| e.map(|e| e.code)),
+            Err(nom::Err::Error(nom::error::ErrorKind::TooLarge))
+        );
+
         // Empty value
         assert_eq!(
             uleb128(&[]),
@@ -276,6 +651,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn osu_string_with_encoding_uses_decoder() {
+        // Latin-1, where each byte maps directly to a character
+        let latin1 = |bytes: &[u8]| Some(bytes.iter().map(|&b| b as char).collect());
+
+        // "café" in Latin-1, which isn't valid UTF-8
+        let input = [0x0b, 0x04, b'c', b'a', b'f', 0xE9, 0x01];
+
+        assert!(osu_string(&input).is_err());
+        assert_eq!(
+            osu_string_with_encoding(&input, latin1),
+            Ok((&[0x01][..], Some("café".to_string())))
+        );
+
+        // The empty string marker doesn't use the decoder
+        assert_eq!(
+            osu_string_with_encoding(&[0x00], |_| None),
+            Ok((&[][..], None))
+        );
+
+        // Parsing fails if the decoder does
+        assert!(osu_string_with_encoding(&[0x0b, 0x01, b'a'], |_| None).is_err());
+    }
+
     #[test]
     fn windows_datetime_decoding_works() {
         // 07/28/2023 15:30:20 +00:00 ==> 638261550200000000 ticks
@@ -293,4 +692,124 @@ mod tests {
             Ok((&[0x01, 0x02, 0x03][..], datetime))
         );
     }
+
+    #[test]
+    fn numbers_are_decoded_as_little_endian() {
+        // Bytes are written out explicitly, so these don't depend on the host's endianness
+        assert_eq!(
+            modifiers(&[0x48, 0x00, 0x00, 0x00]),
+            Ok((&[][..], Mods::Hidden | Mods::DoubleTime))
+        );
+        assert_eq!(
+            windows_datetime(&[0x00, 0x0E, 0x76, 0x8D, 0x7F, 0x8F, 0xDB, 0x08]),
+            Ok((&[][..], datetime!(2023-07-28 15:30:20 UTC)))
+        );
+        assert_eq!(uleb128(&[0xE5, 0x8E, 0x26]), Ok((&[][..], 624485)));
+    }
+
+    #[test]
+    fn windows_datetime_sub_tick_precision_is_exact() {
+        // Each tick is 100ns, so the remainder of ticks / 10 maps to the sub-microsecond nanoseconds
+        for remainder in 0..10u64 {
+            let ticks = 638261550200000000u64 + remainder;
+            let (_, datetime) = windows_datetime(&ticks.to_le_bytes()).unwrap();
+
+            assert_eq!(datetime.nanosecond(), remainder as u32 * 100);
+            assert_eq!(
+                datetime.replace_nanosecond(0).unwrap(),
+                datetime!(2023-07-28 15:30:20 UTC)
+            );
+        }
+
+        // Millisecond + microsecond + sub-microsecond components
+        let ticks = 638261550201234567u64;
+        let (_, datetime) = windows_datetime(&ticks.to_le_bytes()).unwrap();
+        assert_eq!(datetime.millisecond(), 123);
+        assert_eq!(datetime.microsecond(), 123456);
+        assert_eq!(datetime.nanosecond(), 123456700);
+
+        // Encoding should be lossless for every tick value
+        let mut output = Vec::new();
+        write_windows_datetime(&mut output, datetime);
+        assert_eq!(output, ticks.to_le_bytes());
+    }
+
+    #[test]
+    fn uleb128_encoding_works() {
+        let mut output = Vec::new();
+        write_uleb128(&mut output, 624485);
+        assert_eq!(output, vec![0xE5, 0x8E, 0x26]);
+
+        for value in [0, 1, 127, 128, 16383, 16384, usize::MAX] {
+            let mut output = Vec::new();
+            write_uleb128(&mut output, value);
+            assert_eq!(uleb128(&output), Ok((&[][..], value)));
+        }
+    }
+
+    #[test]
+    fn osu_string_encoding_works() {
+        for value in [None, Some(String::new()), Some("test".to_string())] {
+            let mut output = Vec::new();
+            write_osu_string(&mut output, &value);
+            assert_eq!(osu_string(&output), Ok((&[][..], value)));
+        }
+
+        let mut output = Vec::new();
+        write_osu_string(&mut output, &None);
+        write_osu_string(&mut output, &Some(String::new()));
+        assert_eq!(output, vec![0x00, 0x0b, 0x00]);
+    }
+
+    #[test]
+    fn windows_datetime_encoding_clamps_pre_epoch_values() {
+        let mut output = Vec::new();
+        write_windows_datetime(&mut output, datetime!(0000-12-31 23:59:59 UTC));
+        assert_eq!(output, 0u64.to_le_bytes());
+        assert_eq!(windows_datetime(&output), Ok((&[][..], WINDOWS_EPOCH)));
+    }
+
+    #[test]
+    fn windows_datetime_encoding_works() {
+        let mut output = Vec::new();
+        write_windows_datetime(&mut output, datetime!(2023-07-28 15:30:20 UTC));
+        assert_eq!(output, 638261550200000000u64.to_le_bytes());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversions_are_correct() {
+        use chrono::{TimeZone, Utc};
+        use chrono_interop::{from_chrono, to_chrono};
+
+        let value = datetime!(2023-07-28 15:30:20.1234567 UTC);
+        let expected = Utc.with_ymd_and_hms(2023, 7, 28, 15, 30, 20).unwrap()
+            + chrono::Duration::nanoseconds(123_456_700);
+
+        assert_eq!(to_chrono(value), expected);
+        assert_eq!(from_chrono(expected), Some(value));
+
+        // The .NET epoch used for unset datetimes is also supported
+        let epoch = Utc.with_ymd_and_hms(1, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(to_chrono(WINDOWS_EPOCH), epoch);
+        assert_eq!(from_chrono(epoch), Some(WINDOWS_EPOCH));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_unix_timestamp_round_trips() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Timestamped {
+            #[serde(with = "serde_unix_timestamp")]
+            timestamp: OffsetDateTime,
+        }
+
+        let value = Timestamped {
+            timestamp: datetime!(2023-07-28 15:30:20 UTC),
+        };
+
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"timestamp":1690558220}"#);
+        assert_eq!(serde_json::from_str::<Timestamped>(&json).unwrap(), value);
+    }
 }
@@ -0,0 +1,182 @@
+//! Heuristics for identifying osu!'s database files, without parsing their full contents.
+//!
+//! All of the database files start with a version number, and announce the number of entries they contain shortly after.
+//! Reading these is cheap, so it can be done before committing to a full parse, e.g. to report how much work is ahead.
+
+use nom::{number::complete::le_u32, sequence::tuple, IResult};
+
+use crate::{
+    beatmaps::listing_header,
+    collections::{MIN_COLLECTION_SIZE, MIN_MD5_SIZE},
+    common::{gameplay_mode, osu_string, version_to_date},
+};
+
+/// The kinds of database files that can be identified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbKind {
+    /// An `osu.db` file.
+    Beatmaps,
+
+    /// A `collection.db` file.
+    Collections,
+
+    /// A `scores.db` file.
+    Scores,
+}
+
+/// Identifies the kind of database file from its contents, and reads the number of entries it announces.
+///
+/// The count depends on the kind of file:
+///
+/// - [`DbKind::Beatmaps`] => the number of beatmap entries
+/// - [`DbKind::Collections`] => the number of collections
+/// - [`DbKind::Scores`] => the number of beatmaps which have scores
+///
+/// Only the leading fields are read, so a file which is detected may still fail to parse in full.
+/// An empty `scores.db` file can't be told apart from an empty `collection.db` file, so it is reported as the latter.
+///
+/// Returns `None` if the data doesn't look like any of the database files.
+pub fn estimate_entry_count(data: &[u8]) -> Option<(DbKind, u32)> {
+    if let Some(count) = scores_entry_count(data) {
+        Some((DbKind::Scores, count))
+    } else if let Some(count) = collections_entry_count(data) {
+        Some((DbKind::Collections, count))
+    } else {
+        beatmaps_entry_count(data).map(|count| (DbKind::Beatmaps, count))
+    }
+}
+
+/// Checks for a `scores.db` file, by looking at the first score of the first beatmap.
+fn scores_entry_count(data: &[u8]) -> Option<u32> {
+    let (i, (version, count)) = version_and_count(data).ok()?;
+    version_to_date(version)?;
+
+    // Every beatmap in the listing has at least one score, so an empty listing can't be distinguished from other files
+    let (i, (md5, score_count)) = tuple((osu_string, le_u32))(i).ok()?;
+    let (_, (_, score_version)) = tuple((gameplay_mode, le_u32))(i).ok()?;
+
+    let is_scores = count > 0
+        && md5.is_some_and(|md5| md5.len() == 32)
+        && score_count > 0
+        && version_to_date(score_version).is_some();
+
+    is_scores.then_some(count)
+}
+
+/// Checks for a `collection.db` file, by looking at the header of the first collection.
+///
+/// An empty listing must have no trailing data, which also means that an empty `scores.db` file is reported as a `collection.db` file.
+fn collections_entry_count(data: &[u8]) -> Option<u32> {
+    let (i, (version, count)) = version_and_count(data).ok()?;
+    version_to_date(version)?;
+
+    if count == 0 {
+        return i.is_empty().then_some(count);
+    }
+
+    // Collections are always named, which also rules out the account unlocked flag at the same position in osu.db
+    let (i, (name, md5_count)) = tuple((osu_string, le_u32))(i).ok()?;
+    name?;
+
+    // The remaining data must be able to hold the first collection's hashes, as well as the rest of the collections
+    let min_remaining_size = (md5_count as usize)
+        .checked_mul(MIN_MD5_SIZE)?
+        .checked_add((count as usize - 1).checked_mul(MIN_COLLECTION_SIZE)?)?;
+
+    if min_remaining_size > i.len() {
+        return None;
+    }
+
+    // Beatmap hashes are always 32 characters long
+    if md5_count > 0 {
+        let (_, md5) = osu_string(i).ok()?;
+        md5.filter(|md5| md5.len() == 32)?;
+    }
+
+    Some(count)
+}
+
+/// Checks for an `osu.db` file, by reading the fields preceding the beatmap entries.
+fn beatmaps_entry_count(data: &[u8]) -> Option<u32> {
+    // The account unlocked flag is always written as a 0 or 1
+    if !matches!(data.get(8), Some(0x00 | 0x01)) {
+        return None;
+    }
+
+    let (_, ((version, ..), count)) = tuple((listing_header, le_u32))(data).ok()?;
+    version_to_date(version)?;
+
+    Some(count)
+}
+
+/// Parses the version and entry count at the start of a `collection.db` or `scores.db` file.
+fn version_and_count(input: &[u8]) -> IResult<&[u8], (u32, u32)> {
+    tuple((le_u32, le_u32))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beatmaps::tests::{encode_beatmap_listing, sample_beatmap_entry},
+        common::{write_osu_string, GameplayMode},
+        scores::tests::sample_score_replay,
+    };
+
+    const VERSION: u32 = 20191106;
+
+    #[test]
+    fn beatmap_listing_is_detected() {
+        let entries = vec![sample_beatmap_entry(VERSION); 3];
+        let data = encode_beatmap_listing(VERSION, &entries);
+
+        assert_eq!(estimate_entry_count(&data), Some((DbKind::Beatmaps, 3)));
+    }
+
+    #[test]
+    fn collection_listing_is_detected() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+
+        for (name, md5s) in [("Favourites", 2u32), ("Empty", 0)] {
+            write_osu_string(&mut data, &Some(name.to_string()));
+            data.extend_from_slice(&md5s.to_le_bytes());
+
+            for _ in 0..md5s {
+                write_osu_string(&mut data, &Some("0".repeat(32)));
+            }
+        }
+
+        assert_eq!(estimate_entry_count(&data), Some((DbKind::Collections, 2)));
+
+        // Counts which can't fit in the remaining data are rejected
+        let mut overstated = data.clone();
+        overstated[4..8].copy_from_slice(&1000u32.to_le_bytes());
+        assert_eq!(estimate_entry_count(&overstated), None);
+
+        // Empty listings are treated as collections
+        let empty = [VERSION.to_le_bytes(), 0u32.to_le_bytes()].concat();
+        assert_eq!(estimate_entry_count(&empty), Some((DbKind::Collections, 0)));
+    }
+
+    #[test]
+    fn score_listing_is_detected() {
+        let replay = sample_score_replay(GameplayMode::Standard);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        write_osu_string(&mut data, &replay.beatmap_md5);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend(replay.to_bytes());
+
+        assert_eq!(estimate_entry_count(&data), Some((DbKind::Scores, 1)));
+    }
+
+    #[test]
+    fn unknown_data_is_not_detected() {
+        assert_eq!(estimate_entry_count(&[]), None);
+        assert_eq!(estimate_entry_count(&[0xFF; 64]), None);
+    }
+}
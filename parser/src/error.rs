@@ -8,4 +8,96 @@ pub enum Error {
 
     #[error("I/O error occurred: {}", .0)]
     IO(#[from] std::io::Error),
+
+    #[error("Parsing was cancelled")]
+    Cancelled,
+
+    #[error("This doesn't look like a legacy osu! database file")]
+    UnrecognizedFormat,
+
+    #[error("Expected {expected} entries, but the data ended after {found}")]
+    EntryCountMismatch { expected: usize, found: usize },
+
+    #[error("This replay doesn't contain any replay data")]
+    MissingReplayData,
+
+    #[error("Invalid replay data: {}", .0)]
+    InvalidReplayData(String),
+}
+
+impl Error {
+    /// Gets the offset into the parsed data where a parsing error occurred, given the total length of the data.
+    ///
+    /// Returns `None` if this isn't a parsing error, or if more data was needed to continue parsing.
+    pub fn offset(&self, data_len: usize) -> Option<usize> {
+        match self {
+            Error::Parser(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                data_len.checked_sub(e.input.len())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::CollectionListing;
+
+    #[test]
+    fn parser_error_offset_is_correct() {
+        // Version, one collection, then an invalid string marker
+        let mut data = 20150203u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&[0x05, 0x01, 0x02, 0x03, 0x04]);
+
+        let error = CollectionListing::from_bytes(&data).unwrap_err();
+        assert_eq!(error.offset(data.len()), Some(8));
+    }
+
+    #[test]
+    fn error_source_exposes_inner_error() {
+        use std::error::Error as _;
+
+        // Parser errors expose the underlying nom error
+        let error = CollectionListing::from_bytes(&20150203u32.to_le_bytes()).unwrap_err();
+        let source = error.source().unwrap();
+        assert!(source
+            .downcast_ref::<nom::Err<nom::error::Error<Vec<u8>>>>()
+            .is_some());
+
+        // I/O errors expose the underlying I/O error
+        let error = CollectionListing::from_file("this/file/does/not/exist.db").unwrap_err();
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        // Errors raised by this crate have no underlying source
+        assert!(Error::Cancelled.source().is_none());
+        assert!(Error::UnrecognizedFormat.source().is_none());
+    }
+
+    #[test]
+    fn sqlite_files_are_unrecognized() {
+        use crate::{beatmaps::BeatmapListing, scores::ScoreListing};
+
+        // osu!lazer stores its data in Realm/SQLite files, rather than the legacy database formats
+        let mut data = b"SQLite format 3\0".to_vec();
+        data.extend_from_slice(&[0x10, 0x00, 0x01, 0x01, 0x00, 0x40, 0x20, 0x20]);
+
+        assert!(matches!(
+            BeatmapListing::from_bytes(&data),
+            Err(Error::UnrecognizedFormat)
+        ));
+        assert!(matches!(
+            CollectionListing::from_bytes(&data),
+            Err(Error::UnrecognizedFormat)
+        ));
+        assert!(matches!(
+            ScoreListing::from_bytes(&data),
+            Err(Error::UnrecognizedFormat)
+        ));
+    }
 }
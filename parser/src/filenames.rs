@@ -0,0 +1,15 @@
+//! The names of osu!'s database files, as found in the osu! installation folder.
+//!
+//! Note that the beatmap database is named `osu!.db` on disk, although it's commonly referred to as `osu.db`.
+
+/// The beatmap database, parsed by [`BeatmapListing`](crate::beatmaps::BeatmapListing).
+pub const OSU_DB: &str = "osu!.db";
+
+/// The collection database, parsed by [`CollectionListing`](crate::collections::CollectionListing).
+pub const COLLECTION_DB: &str = "collection.db";
+
+/// The local scores database, parsed by [`ScoreListing`](crate::scores::ScoreListing).
+pub const SCORES_DB: &str = "scores.db";
+
+/// The database of players seen in multiplayer and chat. This crate doesn't parse it.
+pub const PRESENCE_DB: &str = "presence.db";
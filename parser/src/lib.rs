@@ -1,7 +1,10 @@
 pub mod beatmaps;
 pub mod collections;
 pub mod common;
+pub mod detect;
 pub mod error;
+pub mod filenames;
+pub mod playfield;
 pub mod prelude;
 pub mod scores;
 
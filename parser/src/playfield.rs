@@ -0,0 +1,37 @@
+//! Constants describing the osu! playfield, used when interpreting replay coordinates.
+//!
+//! Replay cursor positions are given in osu! pixels, relative to the top-left corner of the playfield.
+//! In osu!catch only the x coordinate is meaningful, and in osu!mania the x coordinate holds the pressed keys instead.
+//!
+//! See the [replay format] for more details.
+//!
+//! [replay format]: https://osu.ppy.sh/wiki/en/Client/File_formats/osr_%28file_format%29#replay-data
+
+/// Width of the playfield, in osu! pixels.
+pub const WIDTH: f32 = 512.0;
+
+/// Height of the playfield, in osu! pixels.
+pub const HEIGHT: f32 = 384.0;
+
+/// Determines whether a position lies within the playfield (edges inclusive).
+pub fn is_in_bounds(x: f32, y: f32) -> bool {
+    (0.0..=WIDTH).contains(&x) && (0.0..=HEIGHT).contains(&y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_checking_works() {
+        assert!(is_in_bounds(0.0, 0.0));
+        assert!(is_in_bounds(256.0, 192.0));
+        assert!(is_in_bounds(WIDTH, HEIGHT));
+
+        assert!(!is_in_bounds(-0.5, 192.0));
+        assert!(!is_in_bounds(256.0, -0.5));
+        assert!(!is_in_bounds(WIDTH + 0.5, 192.0));
+        assert!(!is_in_bounds(256.0, HEIGHT + 0.5));
+        assert!(!is_in_bounds(f32::NAN, 192.0));
+    }
+}
@@ -1,7 +1,40 @@
 pub use {
-    crate::beatmaps::{BeatmapEntry, BeatmapListing, RankedStatus, StarRating, TimingPoint},
+    crate::beatmaps::{
+        BeatmapEntries, BeatmapEntry, BeatmapListing, BeatmapSettings, RankedStatus, StarRating,
+        TimingPoint, UserPermissions,
+    },
     crate::collections::{Collection, CollectionListing},
     crate::common::{GameplayMode, Mods, OsuString},
     crate::error::Error,
-    crate::scores::{BeatmapScores, ScoreListing, ScoreReplay},
+    crate::scores::{BeatmapScores, LifebarGraph, ScoreListing, ScoreReplay},
+    flagset::FlagSet,
+    time::OffsetDateTime,
 };
+
+#[cfg(test)]
+mod tests {
+    // Only the prelude is in scope here, so this checks that the types of public fields can be named through it
+    use super::*;
+
+    #[test]
+    fn prelude_names_field_types() {
+        fn score_fields(replay: &ScoreReplay) -> (FlagSet<Mods>, OffsetDateTime) {
+            (replay.mods, replay.timestamp)
+        }
+
+        fn listing_fields(listing: &BeatmapListing) -> (FlagSet<UserPermissions>, OffsetDateTime) {
+            (listing.user_permissions, listing.account_unlock_date)
+        }
+
+        let _ = (score_fields, listing_fields);
+    }
+
+    #[test]
+    fn prelude_score_replay_has_player_and_lifebar() {
+        fn replay_details(replay: &ScoreReplay) -> (&OsuString, Option<&LifebarGraph>) {
+            (&replay.player_name, replay.lifebar_graph.as_ref())
+        }
+
+        let _ = replay_details;
+    }
+}
@@ -6,7 +6,7 @@
 //! [osu! wiki]: https://github.com/ppy/osu/wiki/Legacy-database-file-structure#scoresdb
 //! [replay format]: https://osu.ppy.sh/wiki/en/Client/File_formats/osr_%28file_format%29
 
-use std::path::Path;
+use std::{collections::HashSet, path::Path};
 
 use flagset::FlagSet;
 use nom::{
@@ -18,18 +18,21 @@ use nom::{
     sequence::{separated_pair, terminated},
     IResult,
 };
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
 use crate::{
+    beatmaps::BeatmapEntry,
     common::{
-        boolean, gameplay_mode, modifiers, osu_string, windows_datetime, GameplayMode, Grade, Mods,
-        OsuString,
+        boolean, check_version, gameplay_mode, individual_mods, modifiers, osu_string,
+        version_to_date, windows_datetime, write_boolean, write_osu_string, write_windows_datetime,
+        GameplayMode, Grade, Mods, OsuString,
     },
     error::Error,
 };
 
 /// Represents the `scores.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScoreListing {
     /// Version (e.g. 20150204)
     pub version: u32,
@@ -40,6 +43,7 @@ pub struct ScoreListing {
 
 /// Represents a list of scores for a beatmap in the `scores.db` file.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BeatmapScores {
     /// Beatmap MD5 hash
     pub md5: OsuString,
@@ -51,7 +55,8 @@ pub struct BeatmapScores {
 /// Represents an individual replay for a score on a beatmap, either in the `scores.db` file or a `.osr` replay.
 ///
 /// Note that the compressed replay data may not be present, e.g. if this came from the `scores.db` file.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScoreReplay {
     /// osu! gameplay mode
     pub gameplay_mode: GameplayMode,
@@ -100,9 +105,12 @@ pub struct ScoreReplay {
 
     /// Life bar graph (see [replay format details](https://osu.ppy.sh/wiki/en/Client/File_formats/osr_%28file_format%29#format)).
     /// Only present when parsing a `.osr` replay file.
+    ///
+    /// `scores.db` still stores a string in this position, but it is always empty, so the same layout applies to both.
     pub lifebar_graph: Option<LifebarGraph>,
 
     /// Timestamp of replay
+    #[cfg_attr(feature = "serde", serde(with = "time::serde::rfc3339"))]
     pub timestamp: OffsetDateTime,
 
     /// LZMA Compressed replay data. Only present when parsing a `.osr` replay file.
@@ -118,8 +126,37 @@ pub struct ScoreReplay {
     pub additional_mod_info: Option<f64>,
 }
 
+/// Represents the different hit counts recorded for a score/replay.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitType {
+    Hit300,
+    Hit100,
+    Hit50,
+    Geki,
+    Katu,
+    Miss,
+}
+
+/// Represents a single frame of the replay data in a .osr replay file.
+#[cfg(feature = "replay-frames")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayFrame {
+    /// Time of this frame in milliseconds, relative to the start of the song
+    pub time: i64,
+
+    /// Cursor x-position (0 - 512), or the pressed keys in osu!mania
+    pub x: f32,
+
+    /// Cursor y-position (0 - 384)
+    pub y: f32,
+
+    /// Bitwise combination of the keys and mouse buttons pressed
+    pub keys: u32,
+}
+
 /// Represents the lifebar graph in a .osr replay file.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LifebarGraph {
     pub points: Vec<(u32, f32)>,
 }
@@ -128,20 +165,66 @@ impl std::fmt::Display for LifebarGraph {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Each point is represented as a 'time|hp' pair, where each pair is comma separated
         // There should also be a trailing comma at the end of the string
-        write!(
-            f,
-            "{}",
-            self.points
-                .iter()
-                .map(|(t, h)| format!("{}|{},", t, h))
-                .collect::<String>()
-        )
+        for (t, h) in &self.points {
+            write!(f, "{}|{},", t, h)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ScoreReplay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Replay data is usually several kilobytes, so only its length is shown
+        struct ByteCount(usize);
+
+        impl std::fmt::Debug for ByteCount {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "<{} bytes>", self.0)
+            }
+        }
+
+        f.debug_struct("ScoreReplay")
+            .field("gameplay_mode", &self.gameplay_mode)
+            .field("version", &self.version)
+            .field("beatmap_md5", &self.beatmap_md5)
+            .field("player_name", &self.player_name)
+            .field("replay_md5", &self.replay_md5)
+            .field("hits_300", &self.hits_300)
+            .field("hits_100", &self.hits_100)
+            .field("hits_50", &self.hits_50)
+            .field("hits_geki", &self.hits_geki)
+            .field("hits_katu", &self.hits_katu)
+            .field("misses", &self.misses)
+            .field("score", &self.score)
+            .field("max_combo", &self.max_combo)
+            .field("is_perfect_combo", &self.is_perfect_combo)
+            .field("mods", &self.mods)
+            .field("lifebar_graph", &self.lifebar_graph)
+            .field("timestamp", &self.timestamp)
+            .field(
+                "replay_data",
+                &self.replay_data.as_ref().map(|data| ByteCount(data.len())),
+            )
+            .field("online_score_id", &self.online_score_id)
+            .field("additional_mod_info", &self.additional_mod_info)
+            .finish()
     }
 }
 
 impl ScoreListing {
+    /// Creates a score listing from the scores for each beatmap, e.g. for writing a new `scores.db` file.
+    pub fn from_scores(version: u32, scores: Vec<BeatmapScores>) -> ScoreListing {
+        ScoreListing {
+            version,
+            beatmap_scores: scores,
+        }
+    }
+
     /// Parses the contents of a `collection.db` file.
     pub fn from_bytes(data: &[u8]) -> Result<ScoreListing, Error> {
+        check_version(data, 0)?;
+
         let (_, listing) = score_listing(data).map_err(|e| e.to_owned())?;
         Ok(listing)
     }
@@ -151,11 +234,123 @@ impl ScoreListing {
         let data = std::fs::read(path)?;
         Self::from_bytes(&data)
     }
+
+    /// Serializes this listing in the `scores.db` file format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_score_listing(&mut output, self);
+        output
+    }
+
+    /// Interprets the osu! version of this listing as a date. See [`version_to_date`].
+    pub fn version_date(&self) -> Option<Date> {
+        version_to_date(self.version)
+    }
+
+    /// Finds the scores present in this listing that aren't present in an older listing, along with their beatmap's MD5 hash.
+    ///
+    /// Scores are matched by their online score ID, or by their replay MD5 hash and timestamp if they were never submitted online (i.e. the ID is 0).
+    pub fn new_scores_since(&self, older: &ScoreListing) -> Vec<(&str, &ScoreReplay)> {
+        let existing = older
+            .beatmap_scores
+            .iter()
+            .flat_map(|b| b.scores.iter())
+            .map(ScoreMatchKey::from)
+            .collect::<HashSet<_>>();
+
+        self.beatmap_scores
+            .iter()
+            .flat_map(|b| {
+                let md5 = b.md5.as_deref().unwrap_or_default();
+                b.scores.iter().map(move |s| (md5, s))
+            })
+            .filter(|(_, s)| !existing.contains(&ScoreMatchKey::from(*s)))
+            .collect()
+    }
+
+    /// Counts the scores in this listing which have (non-empty) compressed replay data.
+    ///
+    /// `scores.db` never stores replay data, so this should normally be 0 - anything else suggests the listing didn't come from osu! itself.
+    pub fn scores_with_replay_data(&self) -> usize {
+        self.beatmap_scores
+            .iter()
+            .flat_map(|b| b.scores.iter())
+            .filter(|s| s.replay_data.as_ref().is_some_and(|data| !data.is_empty()))
+            .count()
+    }
+
+    /// Iterates over the scores set by a particular player across all beatmaps in this listing.
+    ///
+    /// Player names are compared exactly, i.e. the comparison is case-sensitive.
+    pub fn scores_by_player<'a>(
+        &'a self,
+        player: &'a str,
+    ) -> impl Iterator<Item = &'a ScoreReplay> {
+        self.beatmap_scores
+            .iter()
+            .flat_map(move |b| b.scores_by_player(player))
+    }
+}
+
+impl BeatmapScores {
+    /// Creates the scores for a beatmap with the specified MD5 hash.
+    pub fn new(md5: impl Into<OsuString>, scores: Vec<ScoreReplay>) -> BeatmapScores {
+        BeatmapScores {
+            md5: md5.into(),
+            scores,
+        }
+    }
+
+    /// Iterates over the scores set by a particular player for this beatmap.
+    pub fn scores_by_player<'a>(
+        &'a self,
+        player: &'a str,
+    ) -> impl Iterator<Item = &'a ScoreReplay> {
+        self.scores
+            .iter()
+            .filter(move |s| s.player_name.as_deref() == Some(player))
+    }
+
+    /// Checks whether the grades stored in a beatmap entry match the best grades recomputed from these scores.
+    ///
+    /// Gameplay modes without any scores are not checked. A mismatch usually means that `osu.db` is out of date with `scores.db`.
+    pub fn verify_stored_grades(&self, entry: &BeatmapEntry) -> bool {
+        let recomputed = entry.with(|e| e.update_grade_from_scores(&self.scores));
+
+        [
+            (entry.grade_std, recomputed.grade_std),
+            (entry.grade_taiko, recomputed.grade_taiko),
+            (entry.grade_catch, recomputed.grade_catch),
+            (entry.grade_mania, recomputed.grade_mania),
+        ]
+        .into_iter()
+        .all(|(stored, recomputed)| stored as u8 == recomputed as u8)
+    }
+}
+
+/// Key used for matching scores between two score listings.
+#[derive(Hash, PartialEq, Eq)]
+enum ScoreMatchKey<'a> {
+    Online(u64),
+    Local(Option<&'a str>, OffsetDateTime),
+}
+
+impl<'a> From<&'a ScoreReplay> for ScoreMatchKey<'a> {
+    fn from(score: &'a ScoreReplay) -> Self {
+        if score.online_score_id == 0 {
+            ScoreMatchKey::Local(score.replay_md5.as_deref(), score.timestamp)
+        } else {
+            ScoreMatchKey::Online(score.online_score_id)
+        }
+    }
 }
 
 impl ScoreReplay {
     /// Parses the contents of a `.osr` replay.
     pub fn from_bytes(data: &[u8]) -> Result<ScoreReplay, Error> {
+        // The version follows the gameplay mode
+        check_version(data, 1)?;
+
         let (_, listing) = score_replay(data).map_err(|e| e.to_owned())?;
         Ok(listing)
     }
@@ -166,6 +361,112 @@ impl ScoreReplay {
         Self::from_bytes(&data)
     }
 
+    /// Interprets the osu! version of this score as a date. See [`version_to_date`].
+    pub fn version_date(&self) -> Option<Date> {
+        version_to_date(self.version)
+    }
+
+    /// Serializes this score/replay in the `.osr` replay format, which is also used for entries in `scores.db`.
+    ///
+    /// If no replay data is present, the `0xFFFFFFFF` (-1) length marker is written in its place.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::new();
+        write_score_replay(&mut output, self);
+        output
+    }
+
+    /// Creates a hashable key identifying this score/replay, e.g. for deduplicating scores when merging listings.
+    ///
+    /// The key is made up of the online score ID, replay MD5 hash and timestamp.
+    pub fn identity_key(&self) -> (u64, Option<&str>, OffsetDateTime) {
+        (
+            self.online_score_id,
+            self.replay_md5.as_deref(),
+            self.timestamp,
+        )
+    }
+
+    /// Whether this score/replay was played with any of the specified mods.
+    ///
+    /// Nightcore is treated as including DoubleTime, so asking for DoubleTime matches Nightcore scores, but not vice versa.
+    pub fn mods_contains_any(&self, any_of: FlagSet<Mods>) -> bool {
+        individual_mods(any_of).any(|m| self.mods.contains(m))
+    }
+
+    /// Whether this score/replay was played with all of the specified mods.
+    ///
+    /// Nightcore is treated as including DoubleTime, in the same way as [`ScoreReplay::mods_contains_any`].
+    pub fn mods_contains_all(&self, all_of: FlagSet<Mods>) -> bool {
+        self.mods.contains(all_of)
+    }
+
+    /// The online score ID, or `None` if it's unset (stored as `u64::MAX`, i.e. -1).
+    ///
+    /// Note that scores which were never submitted have an ID of 0 instead, which is returned as-is.
+    pub fn online_score_id_opt(&self) -> Option<u64> {
+        (self.online_score_id != u64::MAX).then_some(self.online_score_id)
+    }
+
+    /// Clones this score/replay without its compressed replay data, e.g. for displaying the score's details.
+    ///
+    /// The replay data is never copied, so this is cheap even for `.osr` replays with large amounts of replay data.
+    pub fn metadata_clone(&self) -> ScoreReplay {
+        // Destructured exhaustively, so that any new fields have to be handled here
+        // NOTE: Don't use `..self.clone()` - that would copy the replay data, only to discard it
+        let ScoreReplay {
+            gameplay_mode,
+            version,
+            beatmap_md5,
+            player_name,
+            replay_md5,
+            hits_300,
+            hits_100,
+            hits_50,
+            hits_geki,
+            hits_katu,
+            misses,
+            score,
+            max_combo,
+            is_perfect_combo,
+            mods,
+            lifebar_graph,
+            timestamp,
+            replay_data: _,
+            online_score_id,
+            additional_mod_info,
+        } = self;
+
+        ScoreReplay {
+            gameplay_mode: *gameplay_mode,
+            version: *version,
+            beatmap_md5: beatmap_md5.clone(),
+            player_name: player_name.clone(),
+            replay_md5: replay_md5.clone(),
+            hits_300: *hits_300,
+            hits_100: *hits_100,
+            hits_50: *hits_50,
+            hits_geki: *hits_geki,
+            hits_katu: *hits_katu,
+            misses: *misses,
+            score: *score,
+            max_combo: *max_combo,
+            is_perfect_combo: *is_perfect_combo,
+            mods: *mods,
+            lifebar_graph: lifebar_graph.clone(),
+            timestamp: *timestamp,
+            replay_data: None,
+            online_score_id: *online_score_id,
+            additional_mod_info: *additional_mod_info,
+        }
+    }
+
+    /// Sets or clears the compressed replay data for this score/replay.
+    ///
+    /// When serialized, `None` is written as the `0xFFFFFFFF` (-1) length marker, and `Some` is written with its actual length.
+    pub fn set_replay_data(&mut self, data: Option<Vec<u8>>) {
+        self.replay_data = data;
+    }
+
     /// Calculates the accuracy percentage for this score/replay, using the formulae from the [osu! wiki](https://osu.ppy.sh/wiki/en/Gameplay/Accuracy).
     pub fn accuracy(&self) -> f64 {
         let accuracy = match self.gameplay_mode {
@@ -219,10 +520,134 @@ impl ScoreReplay {
         accuracy * 100.0
     }
 
+    /// Calculates the accuracy percentage as displayed in-game, i.e. rounded to 2 decimal places.
+    ///
+    /// Values are rounded half away from zero, matching the `0.00` format string used by osu!.
+    /// Note that [`ScoreReplay::grade`] uses the full precision value, so a score displayed as 98.00% may still be graded as an S in osu!catch if its actual accuracy is slightly above 98%.
+    pub fn accuracy_rounded(&self) -> f64 {
+        (self.accuracy() * 100.0).round() / 100.0
+    }
+
+    /// Decompresses and parses the replay data into frames, with absolute times.
+    ///
+    /// The RNG seed frame at the end of newer replays is skipped.
+    /// Returns [`Error::MissingReplayData`] if this replay has no replay data (e.g. for scores from `scores.db`).
+    #[cfg(feature = "replay-frames")]
+    pub fn replay_frames(&self) -> Result<Vec<ReplayFrame>, Error> {
+        let compressed = self
+            .replay_data
+            .as_deref()
+            .ok_or(Error::MissingReplayData)?;
+
+        let mut decompressed = Vec::new();
+        lzma_rs::lzma_decompress(&mut &compressed[..], &mut decompressed)
+            .map_err(|e| Error::InvalidReplayData(e.to_string()))?;
+
+        let decompressed =
+            String::from_utf8(decompressed).map_err(|e| Error::InvalidReplayData(e.to_string()))?;
+
+        let mut time = 0;
+        let mut frames = Vec::new();
+
+        // Each frame is represented as 'w|x|y|z', where w is the time since the previous frame
+        for frame in decompressed.split(',').filter(|f| !f.is_empty()) {
+            let invalid_frame = || Error::InvalidReplayData(format!("invalid frame '{}'", frame));
+            let mut parts = frame.split('|');
+            let mut next_part = || parts.next().ok_or_else(invalid_frame);
+
+            let delta = next_part()?.parse::<i64>().map_err(|_| invalid_frame())?;
+            let x = next_part()?.parse::<f32>().map_err(|_| invalid_frame())?;
+            let y = next_part()?.parse::<f32>().map_err(|_| invalid_frame())?;
+            let keys = next_part()?.parse::<u32>().map_err(|_| invalid_frame())?;
+
+            // The RNG seed is stored as a frame with a special time
+            if delta == -12345 {
+                continue;
+            }
+
+            time += delta;
+            frames.push(ReplayFrame { time, x, y, keys });
+        }
+
+        Ok(frames)
+    }
+
+    /// Writes the replay frames as CSV with a header row, i.e. `time_ms,x,y,keys`. See [`ScoreReplay::replay_frames`].
+    #[cfg(feature = "replay-frames")]
+    pub fn frames_to_csv<W: std::io::Write>(&self, mut w: W) -> Result<(), Error> {
+        let frames = self.replay_frames()?;
+
+        writeln!(w, "time_ms,x,y,keys")?;
+
+        for ReplayFrame { time, x, y, keys } in frames {
+            writeln!(w, "{},{},{},{}", time, x, y, keys)?;
+        }
+
+        Ok(())
+    }
+
+    /// Approximates the length of the play in milliseconds, using the latest time recorded in the lifebar graph.
+    ///
+    /// This is useful when the beatmap isn't available. Returns `None` if there is no lifebar graph (e.g. for scores from `scores.db`) or it has no points.
+    pub fn approximate_duration_ms(&self) -> Option<u32> {
+        self.lifebar_graph
+            .as_ref()?
+            .points
+            .iter()
+            .map(|&(time, _)| time)
+            .max()
+    }
+
+    /// Creates a one-line text summary of this score, e.g. for sharing in chat.
+    ///
+    /// The summary looks like `Player | Artist - Title [Diff] +HDDT | 98.73% | 1234x | S`, where the mods are omitted for NoMod.
+    /// If the beatmap isn't known, it is shown as "Unknown Beatmap".
+    pub fn summary_line(&self, beatmap: Option<&BeatmapEntry>) -> String {
+        let mut beatmap_name =
+            beatmap.map_or_else(|| "Unknown Beatmap".to_string(), |b| b.to_string());
+
+        if !self.mods.is_empty() {
+            beatmap_name.push_str(" +");
+            beatmap_name.push_str(&Mods::acronyms(self.mods));
+        }
+
+        format!(
+            "{} | {} | {:.2}% | {}x | {}",
+            self.player_name.as_deref().unwrap_or_default(),
+            beatmap_name,
+            self.accuracy_rounded(),
+            self.max_combo,
+            self.grade()
+        )
+    }
+
     /// Determines the grade achieved for this replay, using the calculations from the [osu! wiki](https://osu.ppy.sh/wiki/en/Gameplay/Grade).
     pub fn grade(&self) -> Grade {
-        // Determine the initial grade (before modifiers)
-        let initial_grade = match self.gameplay_mode {
+        let initial_grade = self.initial_grade();
+
+        // See if this needs to be converted to a silver SS or silver S rank
+        // This is needed when Hidden, Flashlight or Fade In are present
+        let contains_silver_mod = self.mods.contains(Mods::Hidden)
+            || self.mods.contains(Mods::Flashlight)
+            || self.mods.contains(Mods::FadeIn);
+
+        match (initial_grade, contains_silver_mod) {
+            (Grade::SS, true) => Grade::SilverSS,
+            (Grade::S, true) => Grade::SilverS,
+            (g, _) => g,
+        }
+    }
+
+    /// Recomputes the grade for this replay from its hit counts and mods, e.g. after its mods have been edited.
+    ///
+    /// This is the same as [`ScoreReplay::grade`], since the grade is never stored in the replay itself.
+    pub fn recomputed_grade(&self) -> Grade {
+        self.grade()
+    }
+
+    /// Determines the grade achieved for this replay, before modifiers are taken into account.
+    fn initial_grade(&self) -> Grade {
+        match self.gameplay_mode {
             // Standard:
             // - SS = 100% accuracy
             // - S  = Over 90% 300s, at most 1% 50s, and no misses
@@ -333,20 +758,48 @@ impl ScoreReplay {
                     _ => Grade::D,
                 }
             }
-        };
+        }
+    }
+}
 
-        // See if this needs to be converted to a silver SS or silver S rank
-        // This is needed when Hidden, Flashlight or Fade In are present
-        let contains_silver_mod = self.mods.contains(Mods::Hidden)
-            || self.mods.contains(Mods::Flashlight)
-            || self.mods.contains(Mods::FadeIn);
+/// Gets a label describing what a hit count means in a particular gameplay mode.
+pub fn hit_label(mode: GameplayMode, hit: HitType) -> &'static str {
+    use HitType::*;
 
-        match (initial_grade, contains_silver_mod) {
-            (Grade::SS, true) => Grade::SilverSS,
-            (Grade::S, true) => Grade::SilverS,
-            (g, _) => g,
-        }
+    match (mode, hit) {
+        (_, Miss) => "Misses",
+
+        // Taiko only has Great/Good/Miss; hits_50 in the replay data isn't used
+        (GameplayMode::Taiko, Hit300) => "Greats",
+        (GameplayMode::Taiko, Hit100) => "Goods",
+        (GameplayMode::Taiko, Hit50) => "50s (Unused)",
+
+        // See ScoreReplay::accuracy for how these are used in Catch
+        (GameplayMode::Catch, Hit300) => "Fruits",
+        (GameplayMode::Catch, Hit100) => "Drops",
+        (GameplayMode::Catch, Hit50) => "Droplets",
+        (GameplayMode::Catch, Katu) => "Missed Droplets",
+
+        (GameplayMode::Mania, Geki) => "Rainbow 300s",
+        (GameplayMode::Mania, Katu) => "200s",
+
+        (_, Hit300) => "300s",
+        (_, Hit100) => "100s",
+        (_, Hit50) => "50s",
+        (_, Geki) => "Gekis",
+        (_, Katu) => "Katus",
+    }
+}
+
+/// Counts the scores set in each gameplay mode, indexed by `GameplayMode as usize`.
+pub fn mode_histogram<'a>(scores: impl Iterator<Item = &'a ScoreReplay>) -> [usize; 4] {
+    let mut histogram = [0; 4];
+
+    for score in scores {
+        histogram[score.gameplay_mode as usize] += 1;
     }
+
+    histogram
 }
 
 /// Parses a `scores.db` file.
@@ -455,10 +908,89 @@ fn score_replay(input: &[u8]) -> IResult<&[u8], ScoreReplay> {
     ))
 }
 
+/// Writes a `scores.db` file.
+fn write_score_listing(output: &mut Vec<u8>, listing: &ScoreListing) {
+    output.extend_from_slice(&listing.version.to_le_bytes());
+    output.extend_from_slice(&(listing.beatmap_scores.len() as u32).to_le_bytes());
+
+    for beatmap_scores in &listing.beatmap_scores {
+        write_osu_string(output, &beatmap_scores.md5);
+        output.extend_from_slice(&(beatmap_scores.scores.len() as u32).to_le_bytes());
+
+        for score in &beatmap_scores.scores {
+            write_score_replay(output, score);
+        }
+    }
+}
+
+/// Writes a score in the `scores.db` file or `.osr` replay file format.
+fn write_score_replay(output: &mut Vec<u8>, replay: &ScoreReplay) {
+    output.push(replay.gameplay_mode as u8);
+    output.extend_from_slice(&replay.version.to_le_bytes());
+    write_osu_string(output, &replay.beatmap_md5);
+    write_osu_string(output, &replay.player_name);
+    write_osu_string(output, &replay.replay_md5);
+    output.extend_from_slice(&replay.hits_300.to_le_bytes());
+    output.extend_from_slice(&replay.hits_100.to_le_bytes());
+    output.extend_from_slice(&replay.hits_50.to_le_bytes());
+    output.extend_from_slice(&replay.hits_geki.to_le_bytes());
+    output.extend_from_slice(&replay.hits_katu.to_le_bytes());
+    output.extend_from_slice(&replay.misses.to_le_bytes());
+
+    output.extend_from_slice(&replay.score.to_le_bytes());
+    output.extend_from_slice(&replay.max_combo.to_le_bytes());
+    write_boolean(output, replay.is_perfect_combo);
+    output.extend_from_slice(&replay.mods.bits().to_le_bytes());
+    write_osu_string(
+        output,
+        &replay.lifebar_graph.as_ref().map(|g| g.to_string()),
+    );
+    write_windows_datetime(output, replay.timestamp);
+
+    if let Some(replay_data) = &replay.replay_data {
+        output.extend_from_slice(&(replay_data.len() as u32).to_le_bytes());
+        output.extend_from_slice(replay_data);
+    } else {
+        output.extend_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+    }
+
+    output.extend_from_slice(&replay.online_score_id.to_le_bytes());
+
+    if replay.mods.contains(Mods::TargetPractice) {
+        output.extend_from_slice(&replay.additional_mod_info.unwrap_or_default().to_le_bytes());
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub mod tests {
     use super::*;
 
+    /// Creates a sample score for the specified gameplay mode, with no hits recorded.
+    pub(crate) fn sample_score_replay(gameplay_mode: GameplayMode) -> ScoreReplay {
+        ScoreReplay {
+            gameplay_mode,
+            version: 20150203,
+            beatmap_md5: Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+            player_name: Some("peppy".to_string()),
+            replay_md5: Some("0cc175b9c0f1b6a831c399e269772661".to_string()),
+            hits_300: 0,
+            hits_100: 0,
+            hits_50: 0,
+            hits_geki: 0,
+            hits_katu: 0,
+            misses: 0,
+            score: 0,
+            max_combo: 0,
+            is_perfect_combo: false,
+            mods: Mods::none(),
+            lifebar_graph: None,
+            timestamp: time::macros::datetime!(2023-07-28 15:30:20 UTC),
+            replay_data: None,
+            online_score_id: 0,
+            additional_mod_info: None,
+        }
+    }
+
     #[test]
     fn lifebar_graph_parses_correctly() {
         let empty_bytes = vec![0x00];
@@ -502,6 +1034,696 @@ mod tests {
         );
     }
 
+    #[test]
+    fn score_replay_decoding_works() {
+        // Appends a string in osu!'s format (0x0b, ULEB128 length, UTF-8 bytes)
+        fn push_string(bytes: &mut Vec<u8>, s: &str) {
+            bytes.push(0x0b);
+            bytes.push(s.len() as u8);
+            bytes.extend_from_slice(s.as_bytes());
+        }
+
+        let mods = Mods::Hidden | Mods::TargetPractice;
+        let ticks = 638261550200000000u64;
+        let replay_data = [0x5d, 0x00, 0x00, 0x20, 0x00];
+
+        let mut input = vec![0x00];
+        input.extend_from_slice(&20150203u32.to_le_bytes());
+        push_string(&mut input, "d41d8cd98f00b204e9800998ecf8427e");
+        push_string(&mut input, "peppy");
+        push_string(&mut input, "0cc175b9c0f1b6a831c399e269772661");
+        input.extend_from_slice(&300u16.to_le_bytes());
+        input.extend_from_slice(&100u16.to_le_bytes());
+        input.extend_from_slice(&50u16.to_le_bytes());
+        input.extend_from_slice(&20u16.to_le_bytes());
+        input.extend_from_slice(&10u16.to_le_bytes());
+        input.extend_from_slice(&5u16.to_le_bytes());
+        input.extend_from_slice(&1234567u32.to_le_bytes());
+        input.extend_from_slice(&321u16.to_le_bytes());
+        input.push(0x01);
+        input.extend_from_slice(&mods.bits().to_le_bytes());
+        push_string(&mut input, "1676|1,3732|0.5,");
+        input.extend_from_slice(&ticks.to_le_bytes());
+        input.extend_from_slice(&(replay_data.len() as u32).to_le_bytes());
+        input.extend_from_slice(&replay_data);
+        input.extend_from_slice(&987654321u64.to_le_bytes());
+        input.extend_from_slice(&95.5f64.to_le_bytes());
+
+        // Extra data
+        input.extend_from_slice(&[0x01, 0x02]);
+
+        let (rest, replay) = score_replay(&input).unwrap();
+
+        assert_eq!(rest, &[0x01, 0x02][..]);
+        assert_eq!(replay.gameplay_mode, GameplayMode::Standard);
+        assert_eq!(replay.version, 20150203);
+        assert_eq!(
+            replay.beatmap_md5.as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert_eq!(replay.player_name.as_deref(), Some("peppy"));
+        assert_eq!(
+            replay.replay_md5.as_deref(),
+            Some("0cc175b9c0f1b6a831c399e269772661")
+        );
+        assert_eq!(replay.hits_300, 300);
+        assert_eq!(replay.hits_100, 100);
+        assert_eq!(replay.hits_50, 50);
+        assert_eq!(replay.hits_geki, 20);
+        assert_eq!(replay.hits_katu, 10);
+        assert_eq!(replay.misses, 5);
+        assert_eq!(replay.score, 1234567);
+        assert_eq!(replay.max_combo, 321);
+        assert!(replay.is_perfect_combo);
+        assert_eq!(replay.mods, mods);
+        assert_eq!(
+            replay.lifebar_graph,
+            Some(LifebarGraph {
+                points: vec![(1676, 1.0), (3732, 0.5)],
+            })
+        );
+        assert_eq!(
+            replay.timestamp,
+            time::macros::datetime!(2023-07-28 15:30:20 UTC)
+        );
+        assert_eq!(replay.replay_data, Some(replay_data.to_vec()));
+        assert_eq!(replay.online_score_id, 987654321);
+        assert_eq!(replay.additional_mod_info, Some(95.5));
+    }
+
+    #[test]
+    fn score_listing_header_is_little_endian() {
+        // 20191106 = 0x01341782, followed by a beatmap count of 0
+        let listing =
+            ScoreListing::from_bytes(&[0x82, 0x17, 0x34, 0x01, 0x00, 0x00, 0x00, 0x00]).unwrap();
+
+        assert_eq!(listing.version, 20191106);
+        assert!(listing.beatmap_scores.is_empty());
+    }
+
+    #[test]
+    fn score_listing_decoding_works() {
+        let beatmap_md5 = "d41d8cd98f00b204e9800998ecf8427e";
+
+        let mut input = 20150204u32.to_le_bytes().to_vec();
+        input.extend_from_slice(&1u32.to_le_bytes());
+        input.push(0x0b);
+        input.push(beatmap_md5.len() as u8);
+        input.extend_from_slice(beatmap_md5.as_bytes());
+        input.extend_from_slice(&1u32.to_le_bytes());
+
+        // scores.db-style entry: empty lifebar graph and no replay data
+        input.push(0x01);
+        input.extend_from_slice(&20150204u32.to_le_bytes());
+        input.push(0x0b);
+        input.push(beatmap_md5.len() as u8);
+        input.extend_from_slice(beatmap_md5.as_bytes());
+        input.extend_from_slice(&[0x0b, 0x04]);
+        input.extend_from_slice(b"test");
+        input.push(0x00);
+
+        for hits in [500u16, 20, 0, 0, 0, 1] {
+            input.extend_from_slice(&hits.to_le_bytes());
+        }
+
+        input.extend_from_slice(&1000000u32.to_le_bytes());
+        input.extend_from_slice(&800u16.to_le_bytes());
+        input.push(0x00);
+        input.extend_from_slice(&0u32.to_le_bytes());
+        input.push(0x00);
+        input.extend_from_slice(&638261550200000000u64.to_le_bytes());
+        input.extend_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+        input.extend_from_slice(&0u64.to_le_bytes());
+
+        let listing = ScoreListing::from_bytes(&input).unwrap();
+        assert_eq!(listing.version, 20150204);
+        assert_eq!(listing.beatmap_scores.len(), 1);
+
+        let beatmap_scores = &listing.beatmap_scores[0];
+        assert_eq!(beatmap_scores.md5.as_deref(), Some(beatmap_md5));
+        assert_eq!(beatmap_scores.scores.len(), 1);
+
+        let score = &beatmap_scores.scores[0];
+        assert_eq!(score.gameplay_mode, GameplayMode::Taiko);
+        assert_eq!(score.player_name.as_deref(), Some("test"));
+        assert_eq!(score.replay_md5, None);
+        assert_eq!(score.hits_300, 500);
+        assert_eq!(score.hits_100, 20);
+        assert_eq!(score.misses, 1);
+        assert_eq!(score.score, 1000000);
+        assert_eq!(score.max_combo, 800);
+        assert_eq!(score.mods, Mods::none());
+        assert_eq!(score.lifebar_graph, None);
+        assert_eq!(score.replay_data, None);
+        assert_eq!(score.online_score_id, 0);
+        assert_eq!(score.additional_mod_info, None);
+    }
+
+    #[test]
+    fn score_replay_debug_omits_replay_data() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.replay_data = Some(vec![0xAB; 4096]);
+
+        let debug = format!("{:?}", replay);
+        assert!(debug.contains("replay_data: Some(<4096 bytes>)"));
+        assert!(!debug.contains("171, 171"));
+        assert!(debug.contains("player_name: "));
+
+        replay.replay_data = None;
+        assert!(format!("{:?}", replay).contains("replay_data: None"));
+    }
+
+    #[test]
+    fn score_listing_reads_additional_mod_info_only_for_target_practice() {
+        // Encodes a scores.db-style entry, with the additional mod info appended if specified
+        fn push_score(input: &mut Vec<u8>, mods: FlagSet<Mods>, additional_mod_info: Option<f64>) {
+            input.push(0x00);
+            input.extend_from_slice(&20150204u32.to_le_bytes());
+            input.extend_from_slice(&[0x00, 0x00, 0x00]);
+            input.extend_from_slice(&[0; 12]);
+            input.extend_from_slice(&1000000u32.to_le_bytes());
+            input.extend_from_slice(&800u16.to_le_bytes());
+            input.push(0x00);
+            input.extend_from_slice(&mods.bits().to_le_bytes());
+            input.push(0x00);
+            input.extend_from_slice(&638261550200000000u64.to_le_bytes());
+            input.extend_from_slice(&0xFFFFFFFFu32.to_le_bytes());
+            input.extend_from_slice(&0u64.to_le_bytes());
+
+            if let Some(additional_mod_info) = additional_mod_info {
+                input.extend_from_slice(&additional_mod_info.to_le_bytes());
+            }
+        }
+
+        let mut input = 20150204u32.to_le_bytes().to_vec();
+        input.extend_from_slice(&1u32.to_le_bytes());
+        input.push(0x00);
+        input.extend_from_slice(&3u32.to_le_bytes());
+        push_score(&mut input, Mods::Hidden | Mods::TargetPractice, Some(95.5));
+        push_score(&mut input, Mods::Hidden.into(), None);
+        push_score(&mut input, Mods::TargetPractice.into(), Some(12.25));
+
+        let listing = ScoreListing::from_bytes(&input).unwrap();
+        let scores = &listing.beatmap_scores[0].scores;
+
+        assert_eq!(
+            scores
+                .iter()
+                .map(|s| s.additional_mod_info)
+                .collect::<Vec<_>>(),
+            vec![Some(95.5), None, Some(12.25)]
+        );
+    }
+
+    #[test]
+    fn accuracy_rounded_matches_catch_grade_boundary() {
+        // Exactly 98.00% is an A
+        let mut score = sample_score_replay(GameplayMode::Catch);
+        score.hits_300 = 49;
+        score.misses = 1;
+        assert_eq!(score.accuracy_rounded(), 98.0);
+        assert_eq!(score.grade(), Grade::A);
+
+        // 98.01% is an S
+        score.hits_300 = 9801;
+        score.misses = 199;
+        assert_eq!(score.accuracy_rounded(), 98.01);
+        assert_eq!(score.grade(), Grade::S);
+
+        // 97.996% is displayed as 98.00%, and is still an A
+        score.hits_300 = 24499;
+        score.misses = 501;
+        assert_eq!(score.accuracy_rounded(), 98.0);
+        assert_eq!(score.grade(), Grade::A);
+
+        // 98.004% is also displayed as 98.00%, but is graded from the full accuracy
+        score.hits_300 = 24501;
+        score.misses = 499;
+        assert_eq!(score.accuracy_rounded(), 98.0);
+        assert_eq!(score.grade(), Grade::S);
+    }
+
+    #[test]
+    fn score_replay_encoding_round_trips() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.hits_300 = 100;
+        replay.mods = Mods::Hidden | Mods::TargetPractice;
+        replay.lifebar_graph = Some(LifebarGraph {
+            points: vec![(1676, 1.0), (3732, 0.5)],
+        });
+        replay.additional_mod_info = Some(95.5);
+        replay.set_replay_data(Some(vec![0x5d, 0x00, 0x00]));
+
+        let bytes = replay.to_bytes();
+        let decoded = ScoreReplay::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.replay_data, Some(vec![0x5d, 0x00, 0x00]));
+        assert_eq!(decoded.lifebar_graph, replay.lifebar_graph);
+        assert_eq!(decoded.additional_mod_info, Some(95.5));
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn cleared_replay_data_writes_length_marker() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.set_replay_data(Some(vec![0x01, 0x02, 0x03]));
+        replay.set_replay_data(None);
+
+        // Replay data length is followed by the online score ID
+        let bytes = replay.to_bytes();
+        let length = &bytes[bytes.len() - 12..bytes.len() - 8];
+        assert_eq!(length, &[0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let decoded = ScoreReplay::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.replay_data, None);
+
+        // Zero-length replay data is distinct from no replay data
+        replay.set_replay_data(Some(Vec::new()));
+        let bytes = replay.to_bytes();
+        let length = &bytes[bytes.len() - 12..bytes.len() - 8];
+        assert_eq!(length, &[0x00, 0x00, 0x00, 0x00]);
+    }
+
+    #[cfg(feature = "replay-frames")]
+    #[test]
+    fn frames_to_csv_works() {
+        let frames = "0|256|-500|0,-1|256|-500|0,16|100.5|200|1,17|110|210.25|5,-12345|0|0|7364,";
+
+        let mut compressed = Vec::new();
+        lzma_rs::lzma_compress(&mut frames.as_bytes(), &mut compressed).unwrap();
+
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.replay_data = Some(compressed);
+
+        let mut csv = Vec::new();
+        replay.frames_to_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time_ms,x,y,keys\n0,256,-500,0\n-1,256,-500,0\n15,100.5,200,1\n32,110,210.25,5\n"
+        );
+
+        replay.replay_data = None;
+        assert!(matches!(
+            replay.frames_to_csv(Vec::new()),
+            Err(Error::MissingReplayData)
+        ));
+
+        replay.replay_data = Some(vec![0x00, 0x01, 0x02]);
+        assert!(matches!(
+            replay.frames_to_csv(Vec::new()),
+            Err(Error::InvalidReplayData(_))
+        ));
+    }
+
+    #[test]
+    fn approximate_duration_uses_last_lifebar_point() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.lifebar_graph = Some(LifebarGraph {
+            points: vec![(1676, 1.0), (95000, 0.75), (3732, 0.5)],
+        });
+        assert_eq!(replay.approximate_duration_ms(), Some(95000));
+
+        replay.lifebar_graph = Some(LifebarGraph { points: Vec::new() });
+        assert_eq!(replay.approximate_duration_ms(), None);
+
+        replay.lifebar_graph = None;
+        assert_eq!(replay.approximate_duration_ms(), None);
+    }
+
+    #[test]
+    fn summary_line_is_correct() {
+        use crate::beatmaps::tests::sample_beatmap_entry;
+
+        let mut score = sample_score_replay(GameplayMode::Standard);
+        score.player_name = Some("Player".to_string());
+        score.hits_300 = 95;
+        score.hits_100 = 5;
+        score.max_combo = 1234;
+        score.mods = Mods::Hidden | Mods::DoubleTime;
+
+        let beatmap = sample_beatmap_entry(20191106);
+        assert_eq!(
+            score.summary_line(Some(&beatmap)),
+            "Player | Artist - Title [Insane] +HDDT | 96.67% | 1234x | S+"
+        );
+
+        score.mods = Mods::none();
+        assert_eq!(
+            score.summary_line(None),
+            "Player | Unknown Beatmap | 96.67% | 1234x | S"
+        );
+    }
+
+    #[test]
+    fn verify_stored_grades_works() {
+        use crate::beatmaps::tests::sample_beatmap_entry;
+
+        let mut perfect = sample_score_replay(GameplayMode::Standard);
+        perfect.hits_300 = 100;
+
+        let mut missed = sample_score_replay(GameplayMode::Standard);
+        missed.hits_300 = 90;
+        missed.misses = 10;
+
+        let scores = BeatmapScores::new(
+            Some("d41d8cd98f00b204e9800998ecf8427e".to_string()),
+            vec![missed, perfect],
+        );
+
+        let mut entry = sample_beatmap_entry(20191106);
+        entry.grade_std = Grade::SS;
+        entry.grade_taiko = Grade::Unplayed;
+        assert!(scores.verify_stored_grades(&entry));
+
+        // A silver SS alongside a regular SS should be checked the same way in either order
+        let mut silver = scores.scores[1].clone();
+        silver.mods = Mods::Hidden.into();
+
+        entry.grade_std = Grade::SilverSS;
+        for order in [
+            vec![scores.scores[1].clone(), silver.clone()],
+            vec![silver, scores.scores[1].clone()],
+        ] {
+            let scores = BeatmapScores::new(scores.md5.clone(), order);
+            assert!(scores.verify_stored_grades(&entry));
+        }
+
+        // Stale grade for a mode with scores
+        entry.grade_std = Grade::A;
+        assert!(!scores.verify_stored_grades(&entry));
+
+        // Modes without scores aren't checked
+        entry.grade_std = Grade::SS;
+        entry.grade_mania = Grade::B;
+        assert!(scores.verify_stored_grades(&entry));
+    }
+
+    #[test]
+    fn new_scores_since_finds_added_score() {
+        let mut online = sample_score_replay(GameplayMode::Standard);
+        online.online_score_id = 100;
+
+        let local = sample_score_replay(GameplayMode::Standard);
+
+        let mut older = ScoreListing {
+            version: 20150204,
+            beatmap_scores: vec![BeatmapScores {
+                md5: Some("beatmap1".to_string()),
+                scores: vec![online.clone(), local.clone()],
+            }],
+        };
+
+        // Identical listings have no new scores
+        assert!(older.new_scores_since(&older).is_empty());
+
+        // An unsubmitted score with the same replay MD5 but a later timestamp should be new
+        let mut added = local.clone();
+        added.timestamp += time::Duration::minutes(5);
+
+        let mut newer = older.clone();
+        newer.beatmap_scores.push(BeatmapScores {
+            md5: Some("beatmap2".to_string()),
+            scores: vec![added.clone()],
+        });
+
+        let new_scores = newer.new_scores_since(&older);
+        assert_eq!(new_scores.len(), 1);
+        assert_eq!(new_scores[0].0, "beatmap2");
+        assert_eq!(new_scores[0].1.timestamp, added.timestamp);
+
+        // Online scores are matched by ID alone
+        older.beatmap_scores[0].scores[0].timestamp += time::Duration::minutes(5);
+        assert_eq!(newer.new_scores_since(&older).len(), 1);
+    }
+
+    #[test]
+    fn silver_grades_include_perfect_scores() {
+        let mut score = sample_score_replay(GameplayMode::Standard);
+        score.hits_300 = 100;
+        assert_eq!(score.grade(), Grade::SS);
+
+        // Adding Hidden should turn an SS into a silver SS
+        score.mods = Mods::Hidden.into();
+        assert_eq!(score.recomputed_grade(), Grade::SilverSS);
+    }
+
+    #[test]
+    fn score_listing_constructors_round_trip() {
+        let replay = sample_score_replay(GameplayMode::Taiko);
+        let listing = ScoreListing::from_scores(
+            20150204,
+            vec![BeatmapScores::new(
+                "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+                vec![replay.clone()],
+            )],
+        );
+
+        let bytes = listing.to_bytes();
+        let decoded = ScoreListing::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.version, 20150204);
+        assert_eq!(decoded.beatmap_scores.len(), 1);
+        assert_eq!(
+            decoded.beatmap_scores[0].md5.as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert_eq!(
+            decoded.beatmap_scores[0].scores[0].to_bytes(),
+            replay.to_bytes()
+        );
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        // An empty listing is just the version and a count of 0
+        assert_eq!(
+            ScoreListing::from_scores(20150204, Vec::new()).to_bytes(),
+            [20150204u32.to_le_bytes(), 0u32.to_le_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn player_name_is_parsed_from_scores_db_and_osr() {
+        let mut db_score = sample_score_replay(GameplayMode::Standard);
+        db_score.player_name = Some("Cookiezi".to_string());
+
+        let mut osr_score = sample_score_replay(GameplayMode::Mania);
+        osr_score.player_name = Some("jakads".to_string());
+        osr_score.set_replay_data(Some(vec![0x5D, 0x00, 0x00, 0x20, 0x00]));
+
+        // scores.db entries have no replay data
+        let listing = ScoreListing::from_scores(
+            20150204,
+            vec![BeatmapScores::new(
+                db_score.beatmap_md5.clone(),
+                vec![db_score],
+            )],
+        );
+        let listing = ScoreListing::from_bytes(&listing.to_bytes()).unwrap();
+        assert_eq!(
+            listing.beatmap_scores[0].scores[0].player_name.as_deref(),
+            Some("Cookiezi")
+        );
+
+        // .osr replays include the replay data after the timestamp
+        let replay = ScoreReplay::from_bytes(&osr_score.to_bytes()).unwrap();
+        assert_eq!(replay.player_name.as_deref(), Some("jakads"));
+        assert!(replay.replay_data.is_some());
+    }
+
+    #[test]
+    fn scores_with_replay_data_are_counted() {
+        let score = sample_score_replay(GameplayMode::Standard);
+        let mut listing = ScoreListing::from_scores(
+            20150204,
+            vec![
+                BeatmapScores::new("beatmap1".to_string(), vec![score.clone(), score.clone()]),
+                BeatmapScores::new("beatmap2".to_string(), vec![score.clone()]),
+            ],
+        );
+
+        // Scores read from scores.db have no replay data
+        let listing_from_db = ScoreListing::from_bytes(&listing.to_bytes()).unwrap();
+        assert_eq!(listing_from_db.scores_with_replay_data(), 0);
+
+        // Empty replay data doesn't count
+        listing.beatmap_scores[0].scores[0].set_replay_data(Some(Vec::new()));
+        assert_eq!(listing.scores_with_replay_data(), 0);
+
+        listing.beatmap_scores[0].scores[1].set_replay_data(Some(vec![0x5D, 0x00]));
+        listing.beatmap_scores[1].scores[0].set_replay_data(Some(vec![0x5D, 0x00]));
+        assert_eq!(listing.scores_with_replay_data(), 2);
+    }
+
+    #[test]
+    fn scores_by_player_works() {
+        let score = |player: &str, score: u32| ScoreReplay {
+            player_name: Some(player.to_string()),
+            score,
+            ..sample_score_replay(GameplayMode::Standard)
+        };
+
+        let listing = ScoreListing {
+            version: 20150204,
+            beatmap_scores: vec![
+                BeatmapScores {
+                    md5: Some("beatmap1".to_string()),
+                    scores: vec![
+                        score("peppy", 300),
+                        score("Cookiezi", 200),
+                        score("peppy", 100),
+                    ],
+                },
+                BeatmapScores {
+                    md5: Some("beatmap2".to_string()),
+                    scores: vec![score("Cookiezi", 500)],
+                },
+            ],
+        };
+
+        let scores = |player: &str| {
+            listing
+                .scores_by_player(player)
+                .map(|s| s.score)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(scores("peppy"), vec![300, 100]);
+        assert_eq!(scores("Cookiezi"), vec![200, 500]);
+        assert!(scores("PEPPY").is_empty());
+
+        let beatmap_scores = listing.beatmap_scores[0]
+            .scores_by_player("Cookiezi")
+            .map(|s| s.score)
+            .collect::<Vec<_>>();
+        assert_eq!(beatmap_scores, vec![200]);
+    }
+
+    #[test]
+    fn mods_contains_any_works() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.mods = Mods::Hidden | Mods::Nightcore;
+
+        assert!(replay.mods_contains_any(Mods::Hidden | Mods::HardRock));
+        assert!(replay.mods_contains_any(Mods::DoubleTime.into()));
+        assert!(replay.mods_contains_any(Mods::Nightcore.into()));
+        assert!(!replay.mods_contains_any(Mods::HardRock | Mods::Flashlight));
+        assert!(!replay.mods_contains_any(Mods::none()));
+
+        // DoubleTime alone doesn't count as Nightcore
+        replay.mods = Mods::DoubleTime.into();
+        assert!(!replay.mods_contains_any(Mods::Nightcore.into()));
+        assert!(replay.mods_contains_any(Mods::DoubleTime | Mods::Hidden));
+    }
+
+    #[test]
+    fn mods_contains_all_works() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.mods = Mods::Hidden | Mods::Nightcore;
+
+        assert!(replay.mods_contains_all(Mods::Hidden | Mods::DoubleTime));
+        assert!(replay.mods_contains_all(Mods::Hidden | Mods::Nightcore));
+        assert!(!replay.mods_contains_all(Mods::Hidden | Mods::HardRock));
+        assert!(replay.mods_contains_all(Mods::none()));
+
+        replay.mods = Mods::DoubleTime.into();
+        assert!(!replay.mods_contains_all(Mods::Nightcore.into()));
+    }
+
+    #[test]
+    fn online_score_id_sentinel_is_unset() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+
+        replay.online_score_id = 987654321;
+        assert_eq!(replay.online_score_id_opt(), Some(987654321));
+
+        replay.online_score_id = 0;
+        assert_eq!(replay.online_score_id_opt(), Some(0));
+
+        replay.online_score_id = u64::MAX;
+        assert_eq!(replay.online_score_id_opt(), None);
+    }
+
+    #[test]
+    fn metadata_clone_drops_replay_data() {
+        let mut replay = sample_score_replay(GameplayMode::Standard);
+        replay.set_replay_data(Some(vec![0x5D, 0x00, 0x00, 0x80]));
+
+        let clone = replay.metadata_clone();
+        assert_eq!(clone.replay_data, None);
+        assert_eq!(replay.replay_data, Some(vec![0x5D, 0x00, 0x00, 0x80]));
+
+        // Everything else should be kept as-is
+        replay.set_replay_data(None);
+        assert_eq!(clone.to_bytes(), replay.to_bytes());
+    }
+
+    #[test]
+    fn identity_key_works() {
+        let score = sample_score_replay(GameplayMode::Standard);
+
+        // Scores only differing in non-identity fields should share a key
+        let mut same = score.clone();
+        same.score = 123456;
+        same.set_replay_data(Some(vec![0x01]));
+
+        let mut different_id = score.clone();
+        different_id.online_score_id = 100;
+
+        let mut different_md5 = score.clone();
+        different_md5.replay_md5 = None;
+
+        let mut different_timestamp = score.clone();
+        different_timestamp.timestamp += time::Duration::seconds(1);
+
+        assert_eq!(score.identity_key(), same.identity_key());
+        assert_ne!(score.identity_key(), different_id.identity_key());
+        assert_ne!(score.identity_key(), different_md5.identity_key());
+        assert_ne!(score.identity_key(), different_timestamp.identity_key());
+
+        let scores = [
+            score,
+            same,
+            different_id,
+            different_md5,
+            different_timestamp,
+        ];
+        let keys = scores
+            .iter()
+            .map(ScoreReplay::identity_key)
+            .collect::<HashSet<_>>();
+        assert_eq!(keys.len(), 4);
+    }
+
+    #[test]
+    fn mode_histogram_counts_each_mode() {
+        let scores = [
+            GameplayMode::Standard,
+            GameplayMode::Mania,
+            GameplayMode::Standard,
+            GameplayMode::Catch,
+            GameplayMode::Standard,
+        ]
+        .map(sample_score_replay);
+
+        assert_eq!(mode_histogram(scores.iter()), [3, 0, 1, 1]);
+        assert_eq!(mode_histogram(std::iter::empty()), [0; 4]);
+    }
+
+    #[test]
+    fn hit_labels_are_mode_specific() {
+        use HitType::*;
+
+        assert_eq!(hit_label(GameplayMode::Standard, Geki), "Gekis");
+        assert_eq!(hit_label(GameplayMode::Taiko, Hit100), "Goods");
+        assert_eq!(hit_label(GameplayMode::Catch, Hit50), "Droplets");
+        assert_eq!(hit_label(GameplayMode::Catch, Katu), "Missed Droplets");
+        assert_eq!(hit_label(GameplayMode::Mania, Geki), "Rainbow 300s");
+        assert_eq!(hit_label(GameplayMode::Mania, Katu), "200s");
+        assert_eq!(hit_label(GameplayMode::Mania, Hit300), "300s");
+
+        for mode in GameplayMode::all() {
+            assert_eq!(hit_label(mode, Miss), "Misses");
+        }
+    }
+
     #[test]
     fn lifebar_graph_display_is_correct() {
         let graph = LifebarGraph {
@@ -1,20 +1,40 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use egui::Id;
-use osu_db_parser::{flagset, prelude::*};
+use osu_db_parser::{
+    common::{individual_mods, version_to_date},
+    filenames, flagset,
+    prelude::*,
+};
 
-use crate::widgets::file_dialog::FileDialog;
+use crate::widgets::{date_range::DateRangeFilter, file_dialog::FileDialog};
 
 use self::{
-    beatmap_listing::BeatmapListingView, collection_listing::CollectionListingView,
-    replays::ReplaysView, score_details::ScoreDetailsWindow,
+    beatmap_details::BeatmapDetailsWindow, beatmap_listing::BeatmapListingView,
+    collection_listing::CollectionListingView, replays::ReplaysView,
+    score_details::ScoreDetailsWindow, settings::Settings,
 };
 
 mod beatmap_details;
 mod beatmap_listing;
 mod collection_listing;
+mod replay_comparison;
 mod replays;
 mod score_details;
+mod settings;
+
+/// The database files that are loaded when opening an osu! folder.
+///
+/// Each entry lists alternative names for the same file, where only the first one that's present is loaded.
+/// osu! names its beatmap database `osu!.db`, but `osu.db` is also accepted for renamed copies.
+const OSU_FOLDER_FILES: [&[&str]; 3] = [
+    &[filenames::OSU_DB, LEGACY_OSU_DB],
+    &[filenames::COLLECTION_DB],
+    &[filenames::SCORES_DB],
+];
+
+/// An alternative name for `osu!.db`, which is only loaded if `osu!.db` itself is missing.
+const LEGACY_OSU_DB: &str = "osu.db";
 
 /// Holds the state for the main application.
 pub struct MainApp {
@@ -22,6 +42,11 @@ pub struct MainApp {
     file_dialog: FileDialog,
     pending_file_operation: Option<FileOperation>,
 
+    // Settings
+    settings: Settings,
+    #[cfg(not(target_arch = "wasm32"))]
+    show_settings: bool,
+
     // Views
     current_view: ViewType,
     beatmap_listing: BeatmapListingView,
@@ -29,8 +54,56 @@ pub struct MainApp {
     replays: ReplaysView,
 
     // MD5 Lookups
-    beatmaps: HashMap<String, BeatmapEntry>,
+    beatmaps: BeatmapIndex,
     scores: HashMap<String, Vec<ScoreReplay>>,
+
+    // Errors
+    load_error: Option<LoadError>,
+}
+
+/// Holds the loaded beatmap listing, which is shared with any views and windows that display its beatmaps.
+///
+/// Beatmaps are looked up by MD5 hash through their index in the listing, so that entries aren't duplicated.
+#[derive(Default)]
+pub struct BeatmapIndex {
+    listing: Option<Arc<BeatmapListing>>,
+    md5_indices: HashMap<String, usize>,
+}
+
+impl BeatmapIndex {
+    /// Indexes the beatmaps in a beatmap listing by their MD5 hash.
+    fn new(listing: Arc<BeatmapListing>) -> Self {
+        let md5_indices = listing
+            .beatmaps
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| b.md5.as_ref().map(|md5| (md5.clone(), i)))
+            .collect();
+
+        Self {
+            listing: Some(listing),
+            md5_indices,
+        }
+    }
+
+    /// Looks up a beatmap by its MD5 hash.
+    pub fn get(&self, md5: &str) -> Option<&BeatmapEntry> {
+        let listing = self.listing.as_ref()?;
+        self.md5_indices.get(md5).map(|&i| &listing.beatmaps[i])
+    }
+
+    /// Creates a details window for the beatmap with the specified MD5 hash, if it exists.
+    pub fn details_window(&self, id: Id, md5: &str) -> Option<BeatmapDetailsWindow> {
+        let listing = self.listing.as_ref()?;
+        let index = *self.md5_indices.get(md5)?;
+        Some(BeatmapDetailsWindow::new(id, Arc::clone(listing), index))
+    }
+}
+
+/// Represents an error that occurred when loading a file.
+struct LoadError {
+    message: String,
+    hex_dump: Option<String>,
 }
 
 /// Represents the different 'tabs' that can be navigated to.
@@ -42,6 +115,7 @@ enum ViewType {
 }
 
 /// Represents a file operation requested by the user.
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Copy, Debug)]
 enum FileOperation {
     GetBeatmapListing,
@@ -56,13 +130,19 @@ impl Default for MainApp {
             file_dialog: FileDialog::default(),
             pending_file_operation: None,
 
+            settings: Settings::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            show_settings: false,
+
             current_view: ViewType::BeatmapListing,
             beatmap_listing: BeatmapListingView::default(),
             collection_listing: CollectionListingView::default(),
             replays: ReplaysView::default(),
 
-            beatmaps: HashMap::new(),
+            beatmaps: BeatmapIndex::default(),
             scores: HashMap::new(),
+
+            load_error: None,
         }
     }
 }
@@ -71,95 +151,295 @@ impl eframe::App for MainApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.check_for_files();
         self.menu_bar(ctx, frame);
+        self.load_error_window(ctx);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.settings
+            .view(ctx, &mut self.show_settings, &self.file_dialog);
 
         // Determine which view to show
         match self.current_view {
-            ViewType::BeatmapListing => self.beatmap_listing.view(ctx, &self.scores),
+            ViewType::BeatmapListing => {
+                self.beatmap_listing
+                    .view(ctx, &self.beatmaps, &self.scores, &self.settings)
+            }
             ViewType::CollectionListing => {
                 self.collection_listing
-                    .view(ctx, &self.beatmaps, &self.scores)
+                    .view(ctx, &self.beatmaps, &self.scores, &self.file_dialog)
             }
-            ViewType::Replays => self.replays.view(ctx),
+            ViewType::Replays => self.replays.view(ctx, &self.beatmaps),
         }
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Settings::STORAGE_KEY, &self.settings);
+    }
 }
 
 impl MainApp {
+    /// Creates the application, restoring any settings persisted from a previous session.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self {
+            settings: Settings::load(cc.storage),
+            ..Default::default()
+        }
+    }
+
     /// Checks if we are waiting for a file and attempts to parse it if it has been loaded.
     fn check_for_files(&mut self) {
         if let Some(file_operation) = self.pending_file_operation {
             if let Some(data) = self.file_dialog.get() {
-                match file_operation {
-                    FileOperation::GetBeatmapListing => match BeatmapListing::from_bytes(&data) {
-                        Ok(beatmap_listing) => {
-                            // Setup the MD5 mapping for the loaded beatmaps
-                            self.beatmaps = beatmap_listing
-                                .beatmaps
-                                .iter()
-                                .filter_map(|b| b.md5.as_ref().map(|md5| (md5.clone(), b.clone())))
-                                .collect();
-
-                            // Update any window titles for the replays view
-                            self.replays.update_replay_titles(&self.beatmaps);
-
-                            // Load the beatmap listing and change views
-                            self.beatmap_listing.load_beatmap_listing(beatmap_listing);
-                            self.current_view = ViewType::BeatmapListing;
-                        }
-                        Err(e) => log::warn!("Unable to open beatmap listing: {}", e),
-                    },
-                    FileOperation::GetCollectionListing => {
-                        match CollectionListing::from_bytes(&data) {
-                            Ok(collection_listing) => {
-                                self.collection_listing
-                                    .load_collection_listing(collection_listing);
-                                self.current_view = ViewType::CollectionListing;
-                            }
-                            Err(e) => log::warn!("Unable to open collection listing: {}", e),
-                        }
+                self.load_file(file_operation, &data);
+                self.pending_file_operation = None;
+            }
+        }
+
+        if let Some(files) = self.file_dialog.get_folder() {
+            self.load_folder(files);
+        }
+
+        if let Some(files) = self.file_dialog.get_multiple() {
+            self.load_replays(files);
+        }
+    }
+
+    /// Parses a loaded file and passes it to the relevant view, returning whether it was parsed successfully.
+    fn load_file(&mut self, file_operation: FileOperation, data: &[u8]) -> bool {
+        match file_operation {
+            FileOperation::GetBeatmapListing => match BeatmapListing::from_bytes(data) {
+                Ok(beatmap_listing) => {
+                    // Setup the MD5 mapping for the loaded beatmaps
+                    let beatmap_listing = Arc::new(beatmap_listing);
+                    self.beatmaps = BeatmapIndex::new(Arc::clone(&beatmap_listing));
+
+                    // Update any window titles for the replays view
+                    self.replays.update_replay_titles(&self.beatmaps);
+
+                    // Load the beatmap listing and change views
+                    self.beatmap_listing.load_beatmap_listing(beatmap_listing);
+                    self.current_view = ViewType::BeatmapListing;
+                }
+                Err(e) => {
+                    self.set_load_error("Unable to open beatmap listing", data, e);
+                    return false;
+                }
+            },
+            FileOperation::GetCollectionListing => match CollectionListing::from_bytes(data) {
+                Ok(collection_listing) => {
+                    self.collection_listing
+                        .load_collection_listing(collection_listing);
+                    self.current_view = ViewType::CollectionListing;
+                }
+                Err(e) => {
+                    self.set_load_error("Unable to open collection listing", data, e);
+                    return false;
+                }
+            },
+            FileOperation::GetScoreListing => match ScoreListing::from_bytes(data) {
+                Ok(score_listing) => {
+                    log::info!(
+                        "Successfully loaded scores.db (version: {})",
+                        score_listing.version
+                    );
+
+                    // Setup the MD5 mapping for the loaded scores
+                    self.scores = score_listing
+                        .beatmap_scores
+                        .into_iter()
+                        .filter_map(|s| s.md5.map(|md5| (md5, s.scores)))
+                        .collect();
+
+                    // Order each beatmap's scores by descending score, then ascending date
+                    for beatmap_scores in self.scores.values_mut() {
+                        beatmap_scores.sort_unstable_by(|a, b| {
+                            b.score
+                                .cmp(&a.score)
+                                .then_with(|| a.timestamp.cmp(&b.timestamp))
+                        });
                     }
-                    FileOperation::GetScoreListing => match ScoreListing::from_bytes(&data) {
-                        Ok(score_listing) => {
-                            log::info!(
-                                "Successfully loaded scores.db (version: {})",
-                                score_listing.version
-                            );
-
-                            // Setup the MD5 mapping for the loaded scores
-                            self.scores = score_listing
-                                .beatmap_scores
-                                .into_iter()
-                                .filter_map(|s| s.md5.map(|md5| (md5, s.scores)))
-                                .collect();
-
-                            // Order each beatmap's scores by descending score, then ascending date
-                            for beatmap_scores in self.scores.values_mut() {
-                                beatmap_scores.sort_unstable_by(|a, b| {
-                                    b.score
-                                        .cmp(&a.score)
-                                        .then_with(|| a.timestamp.cmp(&b.timestamp))
-                                });
-                            }
-                        }
-                        Err(e) => log::warn!("Unable to open score listing: {}", e),
-                    },
-                    FileOperation::GetReplay => match ScoreReplay::from_bytes(&data) {
-                        Ok(replay) => {
-                            log::info!(
-                                "Successfully loaded .osr replay (version: {})",
-                                replay.version
-                            );
-
-                            self.replays.load_replay(replay, &self.beatmaps);
-                            self.current_view = ViewType::Replays;
-                        }
-                        Err(e) => log::warn!("Unable to open replay file: {}", e),
-                    },
                 }
+                Err(e) => {
+                    self.set_load_error("Unable to open score listing", data, e);
+                    return false;
+                }
+            },
+            FileOperation::GetReplay => match ScoreReplay::from_bytes(data) {
+                Ok(replay) => {
+                    log::info!(
+                        "Successfully loaded .osr replay (version: {})",
+                        replay.version
+                    );
 
-                self.pending_file_operation = None;
+                    self.replays.load_replay(replay, &self.beatmaps);
+                    self.current_view = ViewType::Replays;
+                }
+                Err(e) => {
+                    self.set_load_error("Unable to open replay file", data, e);
+                    return false;
+                }
+            },
+        }
+
+        true
+    }
+
+    /// Loads the database files read from an osu! folder, matching each file to its format by name.
+    fn load_folder(&mut self, files: Vec<(String, Vec<u8>)>) {
+        if files.is_empty() {
+            self.load_error = Some(LoadError {
+                message: format!(
+                    "No osu! database files were found (expected {})",
+                    OSU_FOLDER_FILES.map(|names| names[0]).join(", ")
+                ),
+                hex_dump: None,
+            });
+
+            return;
+        }
+
+        // Multiple files may be selected on the web, so the fallback name can still show up alongside osu!.db
+        let has_osu_db = files
+            .iter()
+            .any(|(file_name, _)| file_name.eq_ignore_ascii_case(filenames::OSU_DB));
+
+        let mut has_beatmaps = false;
+
+        for (file_name, data) in files {
+            let file_operation = match file_name.to_ascii_lowercase().as_str() {
+                LEGACY_OSU_DB if has_osu_db => {
+                    log::info!(
+                        "Ignoring '{}' in favour of '{}'",
+                        file_name,
+                        filenames::OSU_DB
+                    );
+                    continue;
+                }
+                filenames::OSU_DB | LEGACY_OSU_DB => FileOperation::GetBeatmapListing,
+                filenames::COLLECTION_DB => FileOperation::GetCollectionListing,
+                filenames::SCORES_DB => FileOperation::GetScoreListing,
+                _ => {
+                    log::warn!("Ignoring unrecognised file '{}'", file_name);
+                    continue;
+                }
+            };
+
+            let is_beatmap_listing = matches!(file_operation, FileOperation::GetBeatmapListing);
+            if self.load_file(file_operation, &data) && is_beatmap_listing {
+                has_beatmaps = true;
             }
         }
+
+        // Prefer showing the beatmap listing, regardless of the order the files were loaded in
+        if has_beatmaps {
+            self.current_view = ViewType::BeatmapListing;
+        }
+    }
+
+    /// Loads several `.osr` replays at once, each into its own window in the replays view.
+    ///
+    /// Any failures are reported together in a single error, since later failures would otherwise replace earlier ones.
+    fn load_replays(&mut self, files: Vec<(String, Vec<u8>)>) {
+        let total_count = files.len();
+        let mut loaded_count = 0;
+        let mut failures = Vec::new();
+
+        for (file_name, data) in files {
+            match ScoreReplay::from_bytes(&data) {
+                Ok(replay) => {
+                    self.replays.load_replay(replay, &self.beatmaps);
+                    loaded_count += 1;
+                }
+                Err(e) => {
+                    log::warn!("Unable to open replay file '{}': {}", file_name, e);
+                    failures.push(format!("{}: {}", file_name, e));
+                }
+            }
+        }
+
+        log::info!("Successfully loaded {} .osr replays", loaded_count);
+
+        if !failures.is_empty() {
+            self.load_error = Some(LoadError {
+                message: format!(
+                    "{} of {} replays failed to load:\n{}",
+                    failures.len(),
+                    total_count,
+                    failures.join("\n")
+                ),
+                hex_dump: None,
+            });
+        }
+
+        if loaded_count > 0 {
+            self.current_view = ViewType::Replays;
+        }
+    }
+
+    /// Records an error that occurred when loading a file, so that it can be displayed to the user.
+    fn set_load_error(&mut self, context: &str, data: &[u8], error: Error) {
+        log::warn!("{}: {}", context, error);
+
+        self.load_error = Some(LoadError {
+            message: format!("{}: {}", context, error),
+            hex_dump: error
+                .offset(data.len())
+                .map(|offset| format!("Offset: {:#x}\n{}", offset, hex_dump(data, offset, 64))),
+        });
+    }
+
+    /// Renders a window showing the last error that occurred when loading a file.
+    fn load_error_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.load_error.is_some();
+
+        if let Some(load_error) = &self.load_error {
+            egui::Window::new("Error")
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(&load_error.message);
+
+                    if let Some(hex_dump) = &load_error.hex_dump {
+                        ui.separator();
+                        ui.label(egui::RichText::new(hex_dump).monospace());
+                    }
+                });
+        }
+
+        if !open {
+            self.load_error = None;
+        }
+    }
+
+    /// Whether the current view has any data loaded that can be exported as JSON.
+    fn can_export_json(&self) -> bool {
+        match self.current_view {
+            ViewType::BeatmapListing => self.beatmap_listing.data().is_some(),
+            ViewType::CollectionListing => self.collection_listing.data().is_some(),
+            ViewType::Replays => !self.replays.replays().is_empty(),
+        }
+    }
+
+    /// Exports the data loaded in the current view as pretty-printed JSON.
+    fn export_json(&self) {
+        let (file_name, json) = match self.current_view {
+            ViewType::BeatmapListing => (
+                "osu.json",
+                serde_json::to_vec_pretty(&self.beatmap_listing.data()),
+            ),
+            ViewType::CollectionListing => (
+                "collection.json",
+                serde_json::to_vec_pretty(&self.collection_listing.data()),
+            ),
+            ViewType::Replays => (
+                "replays.json",
+                serde_json::to_vec_pretty(&self.replays.replays()),
+            ),
+        };
+
+        match json {
+            Ok(json) => self.file_dialog.save(file_name, &json),
+            Err(e) => log::warn!("Unable to export as JSON: {}", e),
+        }
     }
 
     /// Renders the top panel showing the menu bar.
@@ -169,6 +449,29 @@ impl MainApp {
                 ui.menu_button("File", |ui| {
                     use FileOperation::*;
 
+                    if ui.button("Open osu! folder...").clicked() {
+                        self.file_dialog.open_folder(&OSU_FOLDER_FILES);
+                        ui.close_menu();
+                    }
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if ui
+                        .add_enabled(
+                            self.settings.osu_folder.is_some(),
+                            egui::Button::new("Open configured osu! folder"),
+                        )
+                        .on_disabled_hover_text("Set the osu! folder in the settings first")
+                        .clicked()
+                    {
+                        if let Some(osu_folder) = &self.settings.osu_folder {
+                            self.file_dialog.read_folder(osu_folder, &OSU_FOLDER_FILES);
+                        }
+
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
                     if ui.button("Open osu.db...").clicked() {
                         self.pending_file_operation = Some(GetBeatmapListing);
                         self.file_dialog.open();
@@ -193,10 +496,31 @@ impl MainApp {
                         ui.close_menu();
                     }
 
+                    if ui.button("Open multiple replays...").clicked() {
+                        self.file_dialog.open_multiple("osu! replays", &["osr"]);
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    ui.add_enabled_ui(self.can_export_json(), |ui| {
+                        if ui.button("Export as JSON...").clicked() {
+                            self.export_json();
+                            ui.close_menu();
+                        }
+                    });
+
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         ui.separator();
 
+                        if ui.button("Settings...").clicked() {
+                            self.show_settings = true;
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+
                         if ui.button("Close").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
@@ -223,23 +547,37 @@ impl MainApp {
     }
 }
 
+/// Opens a beatmap's folder in the OS file explorer, given the path to the osu! `Songs` folder.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_beatmap_folder(songs_folder: &std::path::Path, beatmap: &BeatmapEntry) {
+    let Some(folder_name) = beatmap.folder_name.as_deref() else {
+        return;
+    };
+
+    let path = songs_folder.join(folder_name);
+
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    if let Err(e) = std::process::Command::new(program).arg(&path).spawn() {
+        log::error!("Unable to open beatmap folder '{}': {}", path.display(), e);
+    }
+}
+
 /// Opens a beatmap link in the browser.
 fn open_beatmap_in_browser(beatmap: &BeatmapEntry) {
-    // Fields to populate are:
-    // - Beatmapset ID
-    // - Gameplay Mode - #osu, #taiko, #fruits, #mania
-    // - Difficulty ID
-    let url = format!(
-        "https://osu.ppy.sh/beatmapsets/{}{}/{}",
-        beatmap.beatmap_id,
-        match beatmap.gameplay_mode {
-            GameplayMode::Standard => "#osu",
-            GameplayMode::Taiko => "#taiko",
-            GameplayMode::Catch => "#fruits",
-            GameplayMode::Mania => "#mania",
-        },
-        beatmap.difficulty_id
-    );
+    let Some(url) = beatmap.online_url() else {
+        log::warn!(
+            "Beatmap '{}' hasn't been submitted, so it has no link",
+            beatmap
+        );
+        return;
+    };
 
     if let Err(e) = webbrowser::open(&url) {
         log::error!("Unable to open beatmap link '{}': {}", &url, e);
@@ -248,17 +586,23 @@ fn open_beatmap_in_browser(beatmap: &BeatmapEntry) {
 
 /// Opens a score details link in the browser.
 fn open_score_in_browser(score: &ScoreReplay) {
+    let Some(online_score_id) = score.online_score_id_opt().filter(|&id| id != 0) else {
+        log::warn!("Score hasn't been submitted, so it has no link");
+        return;
+    };
+
     // Fields to populate are:
     // - Gameplay Mode - osu, taiko, fruits, mania
     // - Online Score ID
-    let url = format!("https://osu.ppy.sh/scores/{}/{}",
+    let url = format!(
+        "https://osu.ppy.sh/scores/{}/{}",
         match score.gameplay_mode {
             GameplayMode::Standard => "osu",
             GameplayMode::Taiko => "taiko",
             GameplayMode::Catch => "fruits",
             GameplayMode::Mania => "mania",
         },
-        score.online_score_id
+        online_score_id
     );
 
     if let Err(e) = webbrowser::open(&url) {
@@ -266,67 +610,107 @@ fn open_score_in_browser(score: &ScoreReplay) {
     }
 }
 
-/// Renders a flagset as a more readable string.
-fn flagset_string<F: flagset::Flags>(flags: flagset::FlagSet<F>) -> String {
-    flags
-        .into_iter()
-        .map(|f| format!("{:?}", f))
-        .collect::<Vec<_>>()
-        .join(", ")
+/// Creates a hex dump of the bytes surrounding an offset, with 16 bytes per line.
+/// The byte at the offset is marked with a `>`.
+fn hex_dump(data: &[u8], offset: usize, context: usize) -> String {
+    use std::fmt::Write;
+
+    let start = offset.saturating_sub(context) / 16 * 16;
+    let end = offset.saturating_add(context + 1).min(data.len());
+    let mut output = String::new();
+
+    for line_start in (start..end).step_by(16) {
+        if line_start != start {
+            output.push('\n');
+        }
+
+        let _ = write!(output, "{:08x}:", line_start);
+
+        for (i, byte) in data.iter().enumerate().take(end).skip(line_start).take(16) {
+            let marker = if i == offset { '>' } else { ' ' };
+            let _ = write!(output, "{}{:02x}", marker, byte);
+        }
+    }
+
+    output
 }
 
-/// Renders a mods flagset as a more readable string.
-fn mods_string(mods: flagset::FlagSet<Mods>) -> String {
-    if mods.is_empty() {
-        "NoMod".to_string()
+/// Renders a flagset as a more readable string, using the display name of each flag.
+fn flagset_string<F: flagset::Flags + std::fmt::Display>(flags: FlagSet<F>) -> String {
+    if flags.is_empty() {
+        "None".to_string()
     } else {
-        flagset_string(mods)
+        flags
+            .into_iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
-/// Renders an unsigned u32 value that acts as -1 when it is the maximum value.
-fn maybe_signed_u32(val: u32) -> egui::WidgetText {
-    if val == 0xFFFFFFFF {
-        "-1".into()
+/// Renders a mods flagset as a more readable string.
+fn mods_string(mods: FlagSet<Mods>) -> String {
+    if mods.is_empty() {
+        "NoMod".to_string()
     } else {
-        val.to_string().into()
+        individual_mods(mods)
+            .map(|m| format!("{:?}", m))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
-/// Renders an unsigned u64 value that acts as -1 when it is the maximum value.
-fn maybe_signed_u64(val: u64) -> egui::WidgetText {
-    if val == u64::MAX {
-        "-1".into()
+/// Renders an osu! version number, along with the date it represents (if valid).
+fn version_string(version: u32) -> String {
+    if let Some(date) = version_to_date(version) {
+        format!(
+            "{} ({} {}, {})",
+            version,
+            date.month(),
+            date.day(),
+            date.year()
+        )
     } else {
-        val.to_string().into()
+        version.to_string()
     }
 }
 
 /// Renders an optional string.
+///
+/// Missing values and empty values are shown differently, since osu! stores these differently (see [`OsuString`](osu_db_parser::common::OsuString)).
 fn optional_string<T: std::fmt::Display>(value: &Option<T>) -> egui::WidgetText {
-    if let Some(v) = value {
-        v.to_string().into()
-    } else {
-        egui::RichText::new("N/A").italics().into()
+    match value.as_ref().map(T::to_string) {
+        Some(v) if v.is_empty() => egui::RichText::new("(empty)").italics().into(),
+        Some(v) => v.into(),
+        None => egui::RichText::new("N/A").italics().into(),
     }
 }
 
-/// Renders a leaderboard of scores for a particular beatmap.
+/// Renders a leaderboard of scores for a particular beatmap, along with a filter for the date range of scores shown.
 /// Assumes that the score values are sorted in descending order.
 fn leaderboard(
     ui: &mut egui::Ui,
-    scores: &Vec<ScoreReplay>,
+    scores: &[ScoreReplay],
+    date_filter: &mut DateRangeFilter,
     score_windows: &mut HashMap<String, ScoreDetailsWindow>,
 ) {
+    date_filter.ui(ui);
+
+    // Keep the original leaderboard positions for filtered scores
+    let filtered_scores = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| date_filter.contains(s.timestamp))
+        .collect::<Vec<_>>();
+
     let row_height = ui.text_style_height(&egui::TextStyle::Body);
 
     egui::ScrollArea::both()
         .auto_shrink([false, true])
         .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
-        .show_rows(ui, row_height, scores.len(), |ui, row_range| {
-            for i in row_range {
+        .show_rows(ui, row_height, filtered_scores.len(), |ui, row_range| {
+            for &(i, details) in &filtered_scores[row_range] {
                 // Replays should have an MD5 hash
-                let details = &scores[i];
                 if let Some(replay_md5) = &details.replay_md5 {
                     // TODO: Mod combination
                     let label = format!(
@@ -336,7 +720,7 @@ fn leaderboard(
                         details.grade(),
                         details.player_name.clone().unwrap_or_default(),
                         details.score,
-                        details.accuracy()
+                        details.accuracy_rounded()
                     );
 
                     if ui.selectable_label(false, &label).clicked() {
@@ -346,7 +730,9 @@ fn leaderboard(
                                 id: Id::new("score_details").with(i),
                                 title: label,
                                 visible: true,
-                                data: details.clone(),
+                                data: details.metadata_clone(),
+                                show_raw_mods: false,
+                                pending_pos: None,
                             },
                         );
                     };
@@ -1,24 +1,41 @@
+use std::sync::Arc;
+
 use egui::{Id, RichText};
 use osu_db_parser::prelude::*;
 
-use super::{maybe_signed_u32, mods_string, open_beatmap_in_browser, optional_string};
+use super::{mods_string, open_beatmap_in_browser, optional_string};
 
 /// A window for displaying beatmap details.
 pub struct BeatmapDetailsWindow {
     pub id: Id,
     pub title: String,
     pub visible: bool,
-    pub data: BeatmapEntry,
+    listing: Arc<BeatmapListing>,
+    index: usize,
 }
 
 impl BeatmapDetailsWindow {
+    /// Creates a window for the beatmap at `index` in a shared beatmap listing.
+    pub fn new(id: Id, listing: Arc<BeatmapListing>, index: usize) -> Self {
+        Self {
+            id,
+            title: listing.beatmaps[index].to_string(),
+            visible: true,
+            listing,
+            index,
+        }
+    }
+
     pub fn view(&mut self, ctx: &egui::Context) {
+        let beatmap = &self.listing.beatmaps[self.index];
+        let settings = beatmap.settings();
+
         egui::Window::new(&self.title)
             .id(self.id)
             .open(&mut self.visible)
             .show(ctx, |ui| {
                 if ui.link("View Beatmap Online").clicked() {
-                    open_beatmap_in_browser(&self.data);
+                    open_beatmap_in_browser(beatmap);
                 }
 
                 egui::ScrollArea::both()
@@ -27,90 +44,94 @@ impl BeatmapDetailsWindow {
                     .show(ui, |ui| {
                         egui::Grid::new(self.id.with("grid")).show(ui, |ui| {
                             ui.label("Size");
-                            ui.label(optional_string(&self.data.size));
+                            ui.label(optional_string(&beatmap.size));
                             ui.end_row();
 
                             ui.label("Artist Name");
-                            ui.label(optional_string(&self.data.artist_name));
+                            ui.label(optional_string(&beatmap.artist_name));
                             ui.end_row();
 
                             ui.label("Artist Name (Unicode)");
-                            ui.label(optional_string(&self.data.artist_name_unicode));
+                            ui.label(optional_string(&beatmap.artist_name_unicode));
                             ui.end_row();
 
                             ui.label("Song Title");
-                            ui.label(optional_string(&self.data.song_title));
+                            ui.label(optional_string(&beatmap.song_title));
                             ui.end_row();
 
                             ui.label("Song Title (Unicode)");
-                            ui.label(optional_string(&self.data.song_title_unicode));
+                            ui.label(optional_string(&beatmap.song_title_unicode));
                             ui.end_row();
 
                             ui.label("Creator");
-                            ui.label(optional_string(&self.data.creator_name));
+                            ui.label(optional_string(&beatmap.creator_name));
                             ui.end_row();
 
                             ui.label("Difficulty");
-                            ui.label(optional_string(&self.data.difficulty));
+                            ui.label(optional_string(&beatmap.difficulty));
                             ui.end_row();
 
                             ui.label("Audio Filename");
-                            ui.label(optional_string(&self.data.audio_filename));
+                            ui.label(optional_string(&beatmap.audio_filename));
                             ui.end_row();
 
                             ui.label("MD5");
-                            ui.label(optional_string(&self.data.md5));
+                            ui.label(optional_string(&beatmap.md5));
                             ui.end_row();
 
                             ui.label("Beatmap Filename");
-                            ui.label(optional_string(&self.data.beatmap_filename));
+                            ui.label(optional_string(&beatmap.beatmap_filename));
                             ui.end_row();
 
                             ui.label("Ranked Status");
-                            ui.label(self.data.ranked_status.to_string());
+                            ui.label(beatmap.ranked_status.to_string());
                             ui.end_row();
 
                             ui.label("Hitcircles");
-                            ui.label(self.data.hitcircle_count.to_string());
+                            ui.label(beatmap.hitcircle_count.to_string());
                             ui.end_row();
 
                             ui.label("Sliders");
-                            ui.label(self.data.slider_count.to_string());
+                            ui.label(beatmap.slider_count.to_string());
                             ui.end_row();
 
                             ui.label("Spinners");
-                            ui.label(self.data.spinner_count.to_string());
+                            ui.label(beatmap.spinner_count.to_string());
+                            ui.end_row();
+
+                            ui.label("Total Objects");
+                            ui.label(beatmap.total_objects().to_string());
                             ui.end_row();
 
                             ui.label("Last Modified");
-                            ui.label(self.data.last_modification_time.to_string());
+                            ui.label(beatmap.last_modification_time.to_string());
                             ui.end_row();
 
                             ui.label("Approach Rate");
-                            ui.label(format!("{:.1}", self.data.approach_rate));
+                            ui.label(format!("{:.1}", beatmap.approach_rate));
                             ui.end_row();
 
                             ui.label("Circle Size");
-                            ui.label(format!("{:.1}", self.data.circle_size));
+                            ui.label(format!("{:.1}", beatmap.circle_size));
                             ui.end_row();
 
                             ui.label("HP Drain");
-                            ui.label(format!("{:.1}", self.data.hp_drain));
+                            ui.label(format!("{:.1}", beatmap.hp_drain));
                             ui.end_row();
 
                             ui.label("Overall Difficulty");
-                            ui.label(format!("{:.1}", self.data.overall_difficulty));
+                            ui.label(format!("{:.1}", beatmap.overall_difficulty));
                             ui.end_row();
 
                             ui.label("Slider Velocity");
-                            ui.label(format!("{:.2}", self.data.slider_velocity));
+                            ui.label(format!("{:.2}", beatmap.slider_velocity));
                             ui.end_row();
 
                             Self::star_ratings(
                                 self.id,
                                 ui,
                                 "Star Ratings (Standard)",
-                                &self.data.star_ratings_std,
+                                &beatmap.star_ratings_std,
                             );
                             ui.end_row();
 
@@ -118,7 +139,7 @@ impl BeatmapDetailsWindow {
                                 self.id,
                                 ui,
                                 "Star Ratings (Taiko)",
-                                &self.data.star_ratings_taiko,
+                                &beatmap.star_ratings_taiko,
                             );
                             ui.end_row();
 
@@ -126,7 +147,7 @@ impl BeatmapDetailsWindow {
                                 self.id,
                                 ui,
                                 "Star Ratings (Catch)",
-                                &self.data.star_ratings_ctb,
+                                &beatmap.star_ratings_ctb,
                             );
                             ui.end_row();
 
@@ -134,30 +155,30 @@ impl BeatmapDetailsWindow {
                                 self.id,
                                 ui,
                                 "Star Ratings (Mania)",
-                                &self.data.star_ratings_mania,
+                                &beatmap.star_ratings_mania,
                             );
                             ui.end_row();
 
                             ui.label("Drain Time");
-                            ui.label(format!("{} s", self.data.drain_time));
+                            ui.label(format!("{} s", beatmap.drain_time));
                             ui.end_row();
 
                             ui.label("Total Time");
-                            ui.label(format!("{} ms", self.data.total_time));
+                            ui.label(format!("{} ms", beatmap.total_time));
                             ui.end_row();
 
                             ui.label("Audio Preview Time");
-                            ui.label(format!("{} ms", self.data.audio_preview_time));
+                            ui.label(format!("{} ms", beatmap.audio_preview_time));
                             ui.end_row();
 
                             ui.label("Timing Points");
 
-                            if self.data.timing_points.is_empty() {
+                            if beatmap.timing_points.is_empty() {
                                 ui.label(RichText::new("N/A").italics());
                             } else {
                                 egui::CollapsingHeader::new(format!(
                                     "{} Timing Points",
-                                    self.data.timing_points.len()
+                                    beatmap.timing_points.len()
                                 ))
                                 .id_salt(self.id.with("timing_points"))
                                 .show(ui, |ui| {
@@ -169,7 +190,7 @@ impl BeatmapDetailsWindow {
                                             ui.label("Inherited?");
                                             ui.end_row();
 
-                                            for timing_point in &self.data.timing_points {
+                                            for timing_point in &beatmap.timing_points {
                                                 ui.label(format!("{:.2}", timing_point.bpm));
                                                 ui.label(format!(
                                                     "{:.2}",
@@ -186,107 +207,107 @@ impl BeatmapDetailsWindow {
                             ui.end_row();
 
                             ui.label("Difficulty ID");
-                            ui.label(maybe_signed_u32(self.data.difficulty_id));
+                            ui.label(optional_string(&beatmap.difficulty_id_opt()));
                             ui.end_row();
 
                             ui.label("Beatmap ID");
-                            ui.label(maybe_signed_u32(self.data.beatmap_id));
+                            ui.label(optional_string(&beatmap.beatmap_id_opt()));
                             ui.end_row();
 
                             ui.label("Thread ID");
-                            ui.label(maybe_signed_u32(self.data.thread_id));
+                            ui.label(optional_string(&beatmap.thread_id_opt()));
                             ui.end_row();
 
                             ui.label("Grade (Standard)");
-                            ui.label(self.data.grade_std.to_string());
+                            ui.label(beatmap.grade_std.to_string());
                             ui.end_row();
 
                             ui.label("Grade (Taiko)");
-                            ui.label(self.data.grade_taiko.to_string());
+                            ui.label(beatmap.grade_taiko.to_string());
                             ui.end_row();
 
                             ui.label("Grade (Catch)");
-                            ui.label(self.data.grade_catch.to_string());
+                            ui.label(beatmap.grade_catch.to_string());
                             ui.end_row();
 
                             ui.label("Grade (Mania)");
-                            ui.label(self.data.grade_mania.to_string());
+                            ui.label(beatmap.grade_mania.to_string());
                             ui.end_row();
 
                             ui.label("Local Offset");
-                            ui.label(format!("{} ms", self.data.local_offset));
+                            ui.label(format!("{} ms", beatmap.local_offset));
                             ui.end_row();
 
                             ui.label("Stack Leniency");
-                            ui.label(format!("{:.1}", self.data.stack_leniency));
+                            ui.label(format!("{:.1}", beatmap.stack_leniency));
                             ui.end_row();
 
                             ui.label("Gameplay Mode");
-                            ui.label(self.data.gameplay_mode.to_string());
+                            ui.label(beatmap.gameplay_mode.to_string());
                             ui.end_row();
 
                             ui.label("Song Source");
-                            ui.label(optional_string(&self.data.song_source));
+                            ui.label(optional_string(&beatmap.song_source));
                             ui.end_row();
 
                             ui.label("Song Tags");
-                            ui.label(optional_string(&self.data.song_tags));
+                            ui.label(optional_string(&beatmap.song_tags));
                             ui.end_row();
 
                             ui.label("Online Offset");
-                            ui.label(format!("{} ms", self.data.online_offset));
+                            ui.label(format!("{} ms", beatmap.online_offset));
                             ui.end_row();
 
                             ui.label("Title Font");
-                            ui.label(optional_string(&self.data.font));
+                            ui.label(optional_string(&beatmap.font));
                             ui.end_row();
 
                             ui.label("Last Played");
-                            ui.label(self.data.last_played.to_string());
+                            ui.label(beatmap.last_played.to_string());
                             ui.end_row();
 
                             ui.label("Is osz2?");
-                            ui.label(self.data.is_osz2.to_string());
+                            ui.label(settings.is_osz2.to_string());
                             ui.end_row();
 
                             ui.label("Folder Name");
-                            ui.label(optional_string(&self.data.folder_name));
+                            ui.label(optional_string(&beatmap.folder_name));
                             ui.end_row();
 
                             ui.label("Last Checked Online");
-                            ui.label(self.data.last_checked_online.to_string());
+                            ui.label(beatmap.last_checked_online.to_string());
                             ui.end_row();
 
                             ui.label("Ignore Hitsounds");
-                            ui.label(self.data.ignore_beatmap_hitsounds.to_string());
+                            ui.label(settings.ignore_beatmap_hitsounds.to_string());
                             ui.end_row();
 
                             ui.label("Ignore Skin");
-                            ui.label(self.data.ignore_beatmap_skin.to_string());
+                            ui.label(settings.ignore_beatmap_skin.to_string());
                             ui.end_row();
 
                             ui.label("Disable Storyboard");
-                            ui.label(self.data.disable_storyboard.to_string());
+                            ui.label(settings.disable_storyboard.to_string());
                             ui.end_row();
 
                             ui.label("Disable Video");
-                            ui.label(self.data.disable_video.to_string());
+                            ui.label(settings.disable_video.to_string());
                             ui.end_row();
 
                             ui.label("Visual Override");
-                            ui.label(self.data.visual_override.to_string());
+                            ui.label(settings.visual_override.to_string());
                             ui.end_row();
 
                             ui.label("Unknown Short");
-                            ui.label(optional_string(&self.data.unknown_u16));
+                            ui.label(optional_string(&beatmap.unknown_u16));
                             ui.end_row();
 
                             ui.label("Unknown Int");
-                            ui.label(self.data.unknown_u32.to_string());
+                            ui.label(beatmap.unknown_u32.to_string());
                             ui.end_row();
 
                             ui.label("Mania Scroll Speed");
-                            ui.label(self.data.mania_scroll_speed.to_string());
+                            ui.label(beatmap.mania_scroll_speed.to_string());
                             ui.end_row();
                         })
                     });
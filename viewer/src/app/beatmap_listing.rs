@@ -1,32 +1,118 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use egui::Id;
 use osu_db_parser::prelude::*;
 
+use crate::widgets::date_range::DateRangeFilter;
+
 use super::{
     beatmap_details::BeatmapDetailsWindow, flagset_string, open_beatmap_in_browser,
-    score_details::ScoreDetailsWindow,
+    score_details::ScoreDetailsWindow, settings::Settings, version_string, BeatmapIndex,
 };
 
 /// A view for displaying beatmap listing details.
 #[derive(Default)]
 pub struct BeatmapListingView {
-    data: Option<BeatmapListing>,
+    data: Option<Arc<BeatmapListing>>,
     selected_beatmap_md5: Option<String>,
 
+    tags: Vec<String>,
+    filters: BeatmapFilters,
+    visible_beatmaps: Vec<usize>,
+
     beatmap_windows: HashMap<String, BeatmapDetailsWindow>,
     score_windows: HashMap<String, ScoreDetailsWindow>,
+    score_date_filter: DateRangeFilter,
+}
+
+/// The filters which determine which beatmaps are shown in the beatmap listing view.
+struct BeatmapFilters {
+    selected_tags: Vec<String>,
+    hide_missing_audio: bool,
+
+    filter_scroll_speed: bool,
+    min_scroll_speed: u8,
+    max_scroll_speed: u8,
+    sort_by_scroll_speed: bool,
+}
+
+impl Default for BeatmapFilters {
+    fn default() -> Self {
+        Self {
+            selected_tags: Vec::new(),
+            hide_missing_audio: false,
+
+            filter_scroll_speed: false,
+            min_scroll_speed: 1,
+            max_scroll_speed: 40,
+            sort_by_scroll_speed: false,
+        }
+    }
+}
+
+impl BeatmapFilters {
+    /// Gets the indices of the beatmaps which pass these filters, in display order.
+    fn apply(&self, beatmap_listing: &BeatmapListing) -> Vec<usize> {
+        let beatmaps = &beatmap_listing.beatmaps;
+        let mut indices = beatmaps
+            .iter()
+            .enumerate()
+            .filter(|(_, beatmap)| !self.hide_missing_audio || beatmap.has_audio())
+            .filter(|(_, beatmap)| {
+                let tags = beatmap.song_tags.as_deref().unwrap_or_default();
+                self.selected_tags
+                    .iter()
+                    .all(|selected| tags.split_whitespace().any(|tag| tag == selected))
+            })
+            .filter(|(_, beatmap)| {
+                !self.filter_scroll_speed
+                    || beatmap.mania_scroll_speed_override().is_some_and(|speed| {
+                        (self.min_scroll_speed..=self.max_scroll_speed).contains(&speed)
+                    })
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        if self.sort_by_scroll_speed {
+            indices.sort_by_key(|&i| beatmaps[i].mania_scroll_speed);
+        }
+
+        indices
+    }
 }
 
 impl BeatmapListingView {
     /// Loads a beatmap listing into this view.
-    pub fn load_beatmap_listing(&mut self, beatmap_listing: BeatmapListing) {
+    pub fn load_beatmap_listing(&mut self, beatmap_listing: Arc<BeatmapListing>) {
+        self.tags = beatmap_listing
+            .distinct_tags()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        self.tags.sort_unstable();
+        self.filters.selected_tags.clear();
+        self.visible_beatmaps = self.filters.apply(&beatmap_listing);
+
         self.data = Some(beatmap_listing);
         self.selected_beatmap_md5 = None;
     }
 
+    /// Gets the beatmap listing loaded into this view, if any.
+    pub fn data(&self) -> Option<&BeatmapListing> {
+        self.data.as_deref()
+    }
+
     /// Renders the beatmap listing view.
-    pub fn view(&mut self, ctx: &egui::Context, scores: &HashMap<String, Vec<ScoreReplay>>) {
+    ///
+    /// On desktop, beatmap folders are opened relative to the `Songs` folder in `settings`.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        beatmaps: &BeatmapIndex,
+        scores: &HashMap<String, Vec<ScoreReplay>>,
+        settings: &Settings,
+    ) {
         // Unload any closed windows
         self.beatmap_windows.retain(|_, w| w.visible);
         self.score_windows.retain(|_, w| w.visible);
@@ -37,7 +123,7 @@ impl BeatmapListingView {
         }
 
         for score_window in self.score_windows.values_mut() {
-            score_window.view(ctx);
+            score_window.view(ctx, beatmaps, &mut self.beatmap_windows);
         }
 
         // Render the left panel showing scores for the selected beatmap
@@ -53,7 +139,12 @@ impl BeatmapListingView {
                     .and_then(|md5| scores.get(md5))
                     .filter(|beatmap_scores| !beatmap_scores.is_empty())
                 {
-                    super::leaderboard(ui, scores, &mut self.score_windows)
+                    super::leaderboard(
+                        ui,
+                        scores,
+                        &mut self.score_date_filter,
+                        &mut self.score_windows,
+                    )
                 } else {
                     ui.label("No local scores found");
                 }
@@ -68,7 +159,7 @@ impl BeatmapListingView {
                 // Base Details
                 egui::Grid::new("base_details").show(ui, |ui| {
                     ui.label("Version");
-                    ui.label(beatmap_listing.version.to_string());
+                    ui.label(version_string(beatmap_listing.version));
                     ui.end_row();
 
                     ui.label("Folder Count");
@@ -92,6 +183,75 @@ impl BeatmapListingView {
                     ui.end_row();
                 });
 
+                // Filters
+                let filters = &mut self.filters;
+                let mut filters_changed = ui
+                    .checkbox(&mut filters.hide_missing_audio, "Hide maps with no audio")
+                    .changed();
+
+                ui.horizontal(|ui| {
+                    filters_changed |= ui
+                        .checkbox(&mut filters.filter_scroll_speed, "Mania scroll speed")
+                        .changed();
+
+                    ui.add_enabled_ui(filters.filter_scroll_speed, |ui| {
+                        filters_changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut filters.min_scroll_speed)
+                                    .range(1..=filters.max_scroll_speed),
+                            )
+                            .changed();
+
+                        ui.label("to");
+
+                        filters_changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut filters.max_scroll_speed)
+                                    .range(filters.min_scroll_speed..=40),
+                            )
+                            .changed();
+                    });
+
+                    filters_changed |= ui
+                        .checkbox(&mut filters.sort_by_scroll_speed, "Sort by scroll speed")
+                        .changed();
+                });
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Tags");
+
+                    let mut removed = None;
+
+                    for (i, tag) in filters.selected_tags.iter().enumerate() {
+                        if ui.button(format!("{tag} ❌")).clicked() {
+                            removed = Some(i);
+                        }
+                    }
+
+                    if let Some(i) = removed {
+                        filters.selected_tags.remove(i);
+                        filters_changed = true;
+                    }
+
+                    egui::ComboBox::from_id_salt("b_tag_filter")
+                        .selected_text("Add tag...")
+                        .height(300.0)
+                        .show_ui(ui, |ui| {
+                            for tag in &self.tags {
+                                if !filters.selected_tags.contains(tag)
+                                    && ui.selectable_label(false, tag).clicked()
+                                {
+                                    filters.selected_tags.push(tag.clone());
+                                    filters_changed = true;
+                                }
+                            }
+                        });
+                });
+
+                if filters_changed {
+                    self.visible_beatmaps = filters.apply(beatmap_listing);
+                }
+
                 // Beatmaps
                 ui.collapsing("Beatmaps", |ui| {
                     let row_height = ui.text_style_height(&egui::TextStyle::Body);
@@ -104,20 +264,15 @@ impl BeatmapListingView {
                         .show_rows(
                             ui,
                             row_height,
-                            beatmap_listing.beatmaps.len(),
+                            self.visible_beatmaps.len(),
                             |ui, row_range| {
-                                for i in row_range {
+                                for i in row_range.map(|row| self.visible_beatmaps[row]) {
                                     let beatmap = &beatmap_listing.beatmaps[i];
                                     let md5 = beatmap.md5.clone().unwrap_or_default();
 
                                     // Beatmaps without an MD5 are invalid - most likely a corrupt DB
                                     if !md5.is_empty() {
-                                        let header = format!(
-                                            "{} - {} [{}]",
-                                            &beatmap.artist_name.clone().unwrap_or_default(),
-                                            &beatmap.song_title.clone().unwrap_or_default(),
-                                            &beatmap.difficulty.clone().unwrap_or_default()
-                                        );
+                                        let header = beatmap.to_string();
 
                                         ui.selectable_value(
                                             &mut self.selected_beatmap_md5,
@@ -128,25 +283,54 @@ impl BeatmapListingView {
                                             |ui| {
                                                 if ui.button("Details").clicked() {
                                                     self.beatmap_windows.insert(
-                                                        md5,
-                                                        BeatmapDetailsWindow {
-                                                            id: Id::new("b_beatmap_details")
-                                                                .with(i),
-                                                            title: header,
-                                                            visible: true,
-                                                            data: beatmap.clone(),
-                                                        },
+                                                        md5.clone(),
+                                                        BeatmapDetailsWindow::new(
+                                                            Id::new("b_beatmap_details").with(i),
+                                                            Arc::clone(beatmap_listing),
+                                                            i,
+                                                        ),
                                                     );
 
                                                     ui.close_menu();
                                                 }
 
+                                                if ui.button("View Scores").clicked() {
+                                                    self.selected_beatmap_md5 = Some(md5.clone());
+                                                    ui.close_menu();
+                                                }
+
                                                 if ui.button("View Beatmap Online").clicked() {
-                                                    open_beatmap_in_browser(&beatmap);
+                                                    open_beatmap_in_browser(beatmap);
+                                                    ui.close_menu();
+                                                }
+
+                                                #[cfg(not(target_arch = "wasm32"))]
+                                                if ui
+                                                    .add_enabled(
+                                                        settings.songs_folder().is_some(),
+                                                        egui::Button::new("Open Containing Folder"),
+                                                    )
+                                                    .on_disabled_hover_text(
+                                                        "Set the Songs folder in the settings first",
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    if let Some(songs_folder) = settings.songs_folder() {
+                                                        super::open_beatmap_folder(&songs_folder, beatmap);
+                                                    }
+
                                                     ui.close_menu();
                                                 }
                                             },
                                         );
+                                    } else {
+                                        ui.add_enabled(
+                                            false,
+                                            egui::SelectableLabel::new(
+                                                false,
+                                                format!("Corrupt entry (#{})", i + 1),
+                                            ),
+                                        );
                                     }
                                 }
                             },
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
 use egui::Id;
-use osu_db_parser::prelude::*;
+use osu_db_parser::{filenames, prelude::*};
+
+use crate::widgets::{date_range::DateRangeFilter, file_dialog::FileDialog};
 
 use super::{
     beatmap_details::BeatmapDetailsWindow, open_beatmap_in_browser,
-    score_details::ScoreDetailsWindow,
+    score_details::ScoreDetailsWindow, version_string, BeatmapIndex,
 };
 
 /// A view for displaying collection listing details.
@@ -14,9 +16,11 @@ pub struct CollectionListingView {
     data: Option<CollectionListing>,
     selected_collection: Option<usize>,
     selected_beatmap_md5: Option<String>,
+    new_collection_name: String,
 
     beatmap_windows: HashMap<String, BeatmapDetailsWindow>,
     score_windows: HashMap<String, ScoreDetailsWindow>,
+    score_date_filter: DateRangeFilter,
 }
 
 impl CollectionListingView {
@@ -27,12 +31,20 @@ impl CollectionListingView {
         self.selected_beatmap_md5 = None;
     }
 
+    /// Gets the collection listing loaded into this view, if any.
+    pub fn data(&self) -> Option<&CollectionListing> {
+        self.data.as_ref()
+    }
+
     /// Renders the collection listing view using the specified beatmap listing details.
+    ///
+    /// The loaded collection listing is saved through `file_dialog`.
     pub fn view(
         &mut self,
         ctx: &egui::Context,
-        beatmaps: &HashMap<String, BeatmapEntry>,
+        beatmaps: &BeatmapIndex,
         scores: &HashMap<String, Vec<ScoreReplay>>,
+        file_dialog: &FileDialog,
     ) {
         // Unload any closed windows
         self.beatmap_windows.retain(|_, w| w.visible);
@@ -44,7 +56,7 @@ impl CollectionListingView {
         }
 
         for score_window in self.score_windows.values_mut() {
-            score_window.view(ctx);
+            score_window.view(ctx, beatmaps, &mut self.beatmap_windows);
         }
 
         // Render the left panel showing scores for the selected beatmap
@@ -60,7 +72,12 @@ impl CollectionListingView {
                     .and_then(|md5| scores.get(md5))
                     .filter(|beatmap_scores| !beatmap_scores.is_empty())
                 {
-                    super::leaderboard(ui, scores, &mut self.score_windows)
+                    super::leaderboard(
+                        ui,
+                        scores,
+                        &mut self.score_date_filter,
+                        &mut self.score_windows,
+                    )
                 } else {
                     ui.label("No local scores found");
                 }
@@ -69,13 +86,48 @@ impl CollectionListingView {
 
         // Render the central panel showing collections + beatmaps
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Collection Listing");
+            ui.horizontal(|ui| {
+                ui.heading("Collection Listing");
+
+                ui.add_enabled_ui(self.data.is_some(), |ui| {
+                    if ui.button("Save collection.db").clicked() {
+                        if let Some(collection_listing) = &self.data {
+                            file_dialog
+                                .save(filenames::COLLECTION_DB, &collection_listing.to_bytes());
+                        }
+                    }
+                });
+            });
 
-            if let Some(collection_listing) = &self.data {
+            if let Some(collection_listing) = &mut self.data {
                 // Version Details
                 ui.horizontal(|ui| {
                     ui.label("Version");
-                    ui.label(collection_listing.version.to_string());
+                    ui.label(version_string(collection_listing.version));
+                });
+
+                // New Collection
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_collection_name)
+                            .hint_text("Collection name"),
+                    );
+
+                    let name = self.new_collection_name.trim();
+                    let is_valid_name = !name.is_empty()
+                        && !collection_listing
+                            .collections
+                            .iter()
+                            .any(|c| c.name.as_deref() == Some(name));
+
+                    if ui
+                        .add_enabled(is_valid_name, egui::Button::new("New Collection"))
+                        .clicked()
+                    {
+                        collection_listing.add_collection(name);
+                        self.selected_collection = Some(collection_listing.collections.len() - 1);
+                        self.new_collection_name.clear();
+                    }
                 });
 
                 // Available Collections
@@ -84,8 +136,8 @@ impl CollectionListingView {
                     .selected_text(
                         self.selected_collection
                             .and_then(|i| collection_listing.collections.get(i))
-                            .and_then(|collection| collection.name.as_ref().map(|n| n.as_str()))
-                            .unwrap_or_else(|| "Select collection..."),
+                            .and_then(|collection| collection.name.as_deref())
+                            .unwrap_or("Select collection..."),
                     )
                     .show_ui(ui, |ui| {
                         for (i, collection) in collection_listing.collections.iter().enumerate() {
@@ -97,7 +149,32 @@ impl CollectionListingView {
                         }
                     });
 
+                // Collection Actions
+                if let Some(collection) = self
+                    .selected_collection
+                    .and_then(|i| collection_listing.collections.get(i))
+                {
+                    if ui
+                        .button("Copy links")
+                        .on_hover_text(
+                            "Copies the online links for the installed beatmaps in this collection",
+                        )
+                        .clicked()
+                    {
+                        let index = collection
+                            .valid_md5s()
+                            .filter_map(|md5| beatmaps.get(md5).map(|beatmap| (md5, beatmap)))
+                            .collect();
+
+                        ui.ctx()
+                            .copy_text(collection.to_url_list(&index).join("\n"));
+                    }
+                }
+
                 // Beatmaps in Current Collection
+                // Removals are applied after rendering, since the collection is borrowed while its beatmaps are shown
+                let mut removed_md5 = None;
+
                 if let Some(collection) = self
                     .selected_collection
                     .and_then(|i| collection_listing.collections.get(i))
@@ -106,64 +183,69 @@ impl CollectionListingView {
 
                     egui::ScrollArea::both()
                         .auto_shrink([false, false])
-                        .show_rows(
-                            ui,
-                            row_height,
-                            collection.beatmap_md5s.len(),
-                            |ui, row_range| {
-                                // Beatmaps references without an MD5 are invalid - most likely a corrupt DB
-                                for i in row_range {
-                                    if let Some(md5) = collection.beatmap_md5s[i]
-                                        .as_ref()
-                                        .filter(|md5| !md5.is_empty())
-                                    {
-                                        if let Some(beatmap) = beatmaps.get(md5) {
-                                            let name = format!(
-                                                "{} - {} [{}]",
-                                                &beatmap.artist_name.clone().unwrap_or_default(),
-                                                &beatmap.song_title.clone().unwrap_or_default(),
-                                                &beatmap.difficulty.clone().unwrap_or_default()
-                                            );
-
-                                            ui.selectable_value(
-                                                &mut self.selected_beatmap_md5,
-                                                Some(md5.clone()),
-                                                &name,
-                                            )
-                                            .context_menu(|ui| {
-                                                if ui.button("Details").clicked() {
-                                                    self.beatmap_windows.insert(
-                                                        md5.clone(),
-                                                        BeatmapDetailsWindow {
-                                                            id: Id::new("c_beatmap_details")
-                                                                .with(i),
-                                                            title: name,
-                                                            visible: true,
-                                                            data: beatmap.clone(),
-                                                        },
-                                                    );
-
-                                                    ui.close_menu();
-                                                }
-
-                                                if ui.button("View Beatmap Online").clicked() {
-                                                    open_beatmap_in_browser(&beatmap);
-                                                    ui.close_menu();
-                                                }
-                                            });
-                                        } else {
-                                            ui.add_enabled(
-                                                false,
-                                                egui::SelectableLabel::new(
-                                                    false,
-                                                    format!("Unknown (MD5: {})", md5),
-                                                ),
-                                            );
+                        .show_rows(ui, row_height, collection.len(), |ui, row_range| {
+                            // Beatmaps references without an MD5 are invalid - most likely a corrupt DB
+                            for (i, md5) in collection
+                                .valid_md5s()
+                                .enumerate()
+                                .skip(row_range.start)
+                                .take(row_range.len())
+                            {
+                                if let Some(beatmap) = beatmaps.get(md5) {
+                                    let name = beatmap.to_string();
+
+                                    ui.selectable_value(
+                                        &mut self.selected_beatmap_md5,
+                                        Some(md5.to_string()),
+                                        &name,
+                                    )
+                                    .context_menu(|ui| {
+                                        if ui.button("Details").clicked() {
+                                            if let Some(window) = beatmaps.details_window(
+                                                Id::new("c_beatmap_details").with(i),
+                                                md5,
+                                            ) {
+                                                self.beatmap_windows
+                                                    .insert(md5.to_string(), window);
+                                            }
+
+                                            ui.close_menu();
                                         }
-                                    }
+
+                                        if ui.button("View Beatmap Online").clicked() {
+                                            open_beatmap_in_browser(beatmap);
+                                            ui.close_menu();
+                                        }
+
+                                        if ui.button("Remove from collection").clicked() {
+                                            removed_md5 = Some(md5.to_string());
+                                            ui.close_menu();
+                                        }
+                                    });
+                                } else {
+                                    ui.add_enabled(
+                                        false,
+                                        egui::SelectableLabel::new(
+                                            false,
+                                            format!("Unknown (MD5: {})", md5),
+                                        ),
+                                    );
                                 }
-                            },
-                        );
+                            }
+                        });
+                }
+
+                if let Some(md5) = removed_md5 {
+                    if let Some(collection) = self
+                        .selected_collection
+                        .and_then(|i| collection_listing.collections.get_mut(i))
+                    {
+                        collection.remove_beatmap(&md5);
+                    }
+
+                    if self.selected_beatmap_md5.as_ref() == Some(&md5) {
+                        self.selected_beatmap_md5 = None;
+                    }
                 }
             } else {
                 ui.label("No collection listing loaded...");
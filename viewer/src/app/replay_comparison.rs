@@ -0,0 +1,84 @@
+use egui::{Color32, Id};
+use egui_plot::Legend;
+use osu_db_parser::prelude::*;
+
+use super::{
+    mods_string,
+    score_details::{lifebar_line, lifebar_plot},
+};
+
+/// The colours used for each replay's lifebar in the comparison plot.
+const LIFEBAR_COLOURS: [Color32; 2] = [Color32::LIGHT_BLUE, Color32::LIGHT_RED];
+
+/// A window for comparing two replays side-by-side.
+pub struct ReplayComparisonWindow {
+    pub id: Id,
+    pub visible: bool,
+    titles: [String; 2],
+    replays: [ScoreReplay; 2],
+}
+
+impl ReplayComparisonWindow {
+    /// Creates a window comparing two replays, using the specified titles to tell them apart.
+    pub fn new(id: Id, first: (&str, &ScoreReplay), second: (&str, &ScoreReplay)) -> Self {
+        Self {
+            id,
+            visible: true,
+            titles: [first.0.to_string(), second.0.to_string()],
+            replays: [first.1.metadata_clone(), second.1.metadata_clone()],
+        }
+    }
+
+    /// Renders this window to display both replays' lifebars and stats.
+    pub fn view(&mut self, ctx: &egui::Context) {
+        let [first, second] = &self.replays;
+
+        egui::Window::new(format!(
+            "Compare: {} vs. {}",
+            self.titles[0], self.titles[1]
+        ))
+        .id(self.id)
+        .open(&mut self.visible)
+        .show(ctx, |ui| {
+            if first.beatmap_md5 != second.beatmap_md5 {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "These replays are on different beatmaps",
+                );
+            }
+
+            // Lifebar Graphs
+            lifebar_plot(self.id.with("lifebar_plot"))
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    for ((title, replay), colour) in
+                        self.titles.iter().zip(&self.replays).zip(LIFEBAR_COLOURS)
+                    {
+                        if let Some(lifebar) = &replay.lifebar_graph {
+                            plot_ui.line(lifebar_line(lifebar).color(colour).name(title));
+                        }
+                    }
+                });
+
+            // Stats
+            egui::Grid::new(self.id.with("grid"))
+                .striped(true)
+                .show(ui, |ui| {
+                    let mut row = |label: &str, value: fn(&ScoreReplay) -> String| {
+                        ui.label(label);
+                        ui.label(value(first));
+                        ui.label(value(second));
+                        ui.end_row();
+                    };
+
+                    row("Player", |r| r.player_name.clone().unwrap_or_default());
+                    row("Score", |r| r.score.to_string());
+                    row("Accuracy", |r| format!("{:.2}%", r.accuracy_rounded()));
+                    row("Max Combo", |r| format!("{}x", r.max_combo));
+                    row("Grade", |r| r.grade().to_string());
+                    row("Mods", |r| mods_string(r.mods));
+                    row("Timestamp", |r| r.timestamp.to_string());
+                });
+        });
+    }
+}
@@ -3,61 +3,173 @@ use std::collections::HashMap;
 use egui::Id;
 use osu_db_parser::prelude::*;
 
-use super::score_details::ScoreDetailsWindow;
+use super::{
+    beatmap_details::BeatmapDetailsWindow, replay_comparison::ReplayComparisonWindow,
+    score_details::ScoreDetailsWindow, BeatmapIndex,
+};
+
+/// The offset between each replay window when they're laid out in a cascade.
+const CASCADE_OFFSET: egui::Vec2 = egui::vec2(24.0, 24.0);
 
 /// Represents the "Replays" tabbed view.
 #[derive(Default)]
 pub struct ReplaysView {
     displayed_replays: Vec<ScoreDetailsWindow>,
+    beatmap_windows: HashMap<String, BeatmapDetailsWindow>,
+    comparison_windows: Vec<ReplayComparisonWindow>,
+
+    sort_by_timestamp: bool,
+    needs_layout: bool,
+
+    compared_replays: [Option<Id>; 2],
+
+    // Window IDs are taken from these counters, so that they aren't reused after a window is closed
+    replay_count: usize,
+    comparison_count: usize,
 }
 
 impl ReplaysView {
     /// Loads a replay into this view.
-    pub fn load_replay(&mut self, replay: ScoreReplay, beatmaps: &HashMap<String, BeatmapEntry>) {
-        let id = Id::new("replay_details").with(self.displayed_replays.len());
+    pub fn load_replay(&mut self, replay: ScoreReplay, beatmaps: &BeatmapIndex) {
+        let id = Id::new("replay_details").with(self.replay_count);
+        self.replay_count += 1;
 
         self.displayed_replays.push(ScoreDetailsWindow {
             id,
             title: Self::get_replay_title(&replay, beatmaps),
             visible: true,
             data: replay,
+            show_raw_mods: false,
+            pending_pos: None,
         });
+
+        self.needs_layout |= self.sort_by_timestamp;
     }
 
     /// Updates the window titles for each replay that is currently being displayed.
-    pub fn update_replay_titles(&mut self, beatmaps: &HashMap<String, BeatmapEntry>) {
+    pub fn update_replay_titles(&mut self, beatmaps: &BeatmapIndex) {
         for displayed_replay in self.displayed_replays.iter_mut() {
             displayed_replay.title = Self::get_replay_title(&displayed_replay.data, beatmaps);
         }
     }
 
+    /// Gets the replays that are currently being displayed.
+    pub fn replays(&self) -> Vec<&ScoreReplay> {
+        self.displayed_replays.iter().map(|w| &w.data).collect()
+    }
+
     /// Renders the view for each replay that is currently loaded.
-    pub fn view(&mut self, ctx: &egui::Context) {
+    pub fn view(&mut self, ctx: &egui::Context, beatmaps: &BeatmapIndex) {
         // Unload any replays whose window is closed
         self.displayed_replays.retain(|w| w.visible);
+        self.beatmap_windows.retain(|_, w| w.visible);
+        self.comparison_windows.retain(|w| w.visible);
+
+        // Central Panel with the layout options
+        let origin = egui::CentralPanel::default()
+            .show(ctx, |ui| {
+                if ui
+                    .checkbox(&mut self.sort_by_timestamp, "Sort by timestamp")
+                    .changed()
+                {
+                    self.needs_layout |= self.sort_by_timestamp;
+                }
+
+                self.comparison_picker(ui);
+
+                ui.min_rect().left_bottom() + egui::vec2(0.0, ui.spacing().item_spacing.y)
+            })
+            .inner;
+
+        if std::mem::take(&mut self.needs_layout) {
+            self.layout_replay_windows(ctx, origin);
+        }
 
         // Display the remaining windows
+        for beatmap_window in self.beatmap_windows.values_mut() {
+            beatmap_window.view(ctx);
+        }
+
         for replay_window in self.displayed_replays.iter_mut() {
-            replay_window.view(ctx);
+            replay_window.view(ctx, beatmaps, &mut self.beatmap_windows);
         }
 
-        // Empty Central Panel
-        egui::CentralPanel::default().show(ctx, |_| {});
+        for comparison_window in self.comparison_windows.iter_mut() {
+            comparison_window.view(ctx);
+        }
+    }
+
+    /// Renders the controls for picking two loaded replays to compare.
+    fn comparison_picker(&mut self, ui: &mut egui::Ui) {
+        let find = |id: Option<Id>| self.displayed_replays.iter().find(|w| Some(w.id) == id);
+
+        ui.horizontal(|ui| {
+            ui.label("Compare");
+
+            for (i, compared) in self.compared_replays.iter_mut().enumerate() {
+                let selected_text =
+                    find(*compared).map_or("Select a replay...", |w| w.title.as_str());
+
+                egui::ComboBox::from_id_salt(Id::new("replay_comparison_picker").with(i))
+                    .selected_text(selected_text)
+                    .width(250.0)
+                    .show_ui(ui, |ui| {
+                        for replay_window in &self.displayed_replays {
+                            ui.selectable_value(
+                                compared,
+                                Some(replay_window.id),
+                                &replay_window.title,
+                            );
+                        }
+                    });
+            }
+
+            let pair = match self.compared_replays.map(find) {
+                [Some(first), Some(second)] if first.id != second.id => Some((first, second)),
+                _ => None,
+            };
+
+            if ui
+                .add_enabled(pair.is_some(), egui::Button::new("Compare"))
+                .clicked()
+            {
+                if let Some((first, second)) = pair {
+                    self.comparison_windows.push(ReplayComparisonWindow::new(
+                        Id::new("replay_comparison").with(self.comparison_count),
+                        (&first.title, &first.data),
+                        (&second.title, &second.data),
+                    ));
+
+                    self.comparison_count += 1;
+                }
+            }
+        });
+    }
+
+    /// Sorts the replay windows by their timestamp, and cascades them from `origin` so that the latest replay is on top.
+    fn layout_replay_windows(&mut self, ctx: &egui::Context, origin: egui::Pos2) {
+        self.displayed_replays.sort_by_key(|w| w.data.timestamp);
+
+        ctx.memory_mut(|mem| {
+            for (i, replay_window) in self.displayed_replays.iter_mut().enumerate() {
+                replay_window.pending_pos = Some(origin + CASCADE_OFFSET * i as f32);
+                mem.areas_mut()
+                    .move_to_top(egui::LayerId::new(egui::Order::Middle, replay_window.id));
+            }
+        });
     }
 
     /// Gets the title for a particular replay.
-    fn get_replay_title(replay: &ScoreReplay, beatmaps: &HashMap<String, BeatmapEntry>) -> String {
+    fn get_replay_title(replay: &ScoreReplay, beatmaps: &BeatmapIndex) -> String {
         if let Some(beatmap) = replay
             .beatmap_md5
             .as_ref()
             .and_then(|md5| beatmaps.get(md5))
         {
             format!(
-                "{} - {} - {} [{}]",
+                "{} - {}",
                 replay.player_name.clone().unwrap_or_default(),
-                beatmap.artist_name.clone().unwrap_or_default(),
-                beatmap.song_title.clone().unwrap_or_default(),
-                beatmap.difficulty.clone().unwrap_or_default(),
+                beatmap
             )
         } else {
             format!(
@@ -1,7 +1,15 @@
+use std::collections::HashMap;
+
 use egui::Id;
-use osu_db_parser::prelude::*;
+use osu_db_parser::{
+    prelude::*,
+    scores::{hit_label, HitType},
+};
 
-use super::{maybe_signed_u64, mods_string, optional_string, open_score_in_browser};
+use super::{
+    beatmap_details::BeatmapDetailsWindow, mods_string, open_score_in_browser, optional_string,
+    version_string, BeatmapIndex,
+};
 
 /// A window for displaying score details.
 pub struct ScoreDetailsWindow {
@@ -9,136 +17,217 @@ pub struct ScoreDetailsWindow {
     pub title: String,
     pub visible: bool,
     pub data: ScoreReplay,
+    pub show_raw_mods: bool,
+
+    /// A position to move this window to the next time it's rendered.
+    pub pending_pos: Option<egui::Pos2>,
 }
 
 impl ScoreDetailsWindow {
     /// Renders this window to display score/replay details.
-    pub fn view(&mut self, ctx: &egui::Context) {
-        egui::Window::new(&self.title)
+    ///
+    /// The beatmap for this score is looked up in `beatmaps`, and can be opened as a new window in `beatmap_windows`.
+    pub fn view(
+        &mut self,
+        ctx: &egui::Context,
+        beatmaps: &BeatmapIndex,
+        beatmap_windows: &mut HashMap<String, BeatmapDetailsWindow>,
+    ) {
+        let mut window = egui::Window::new(&self.title)
             .id(self.id)
-            .open(&mut self.visible)
-            .show(ctx, |ui| {
-                ui.add_enabled_ui(self.data.online_score_id != 0, |ui| {
-                    if ui.link("View Score Online").clicked() {
-                        open_score_in_browser(&self.data);
+            .open(&mut self.visible);
+
+        if let Some(pos) = self.pending_pos.take() {
+            window = window.current_pos(pos);
+        }
+
+        window.show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(
+                    self.data.online_score_id_opt().is_some_and(|id| id != 0),
+                    |ui| {
+                        if ui.link("View Score Online").clicked() {
+                            open_score_in_browser(&self.data);
+                        }
+                    },
+                );
+
+                let beatmap_md5 = self
+                    .data
+                    .beatmap_md5
+                    .as_deref()
+                    .filter(|md5| beatmaps.get(md5).is_some());
+
+                ui.add_enabled_ui(beatmap_md5.is_some(), |ui| {
+                    if ui.link("View Beatmap Details").clicked() {
+                        if let Some(md5) = beatmap_md5 {
+                            if let Some(window) =
+                                beatmaps.details_window(Id::new("s_beatmap_details").with(md5), md5)
+                            {
+                                beatmap_windows.insert(md5.to_string(), window);
+                            }
+                        }
                     }
                 });
 
-                egui::Grid::new(self.id.with("grid")).show(ui, |ui| {
-                    ui.label("Gameplay Mode");
-                    ui.label(self.data.gameplay_mode.to_string());
-                    ui.end_row();
-
-                    ui.label("Version");
-                    ui.label(self.data.version.to_string());
-                    ui.end_row();
-
-                    ui.label("Beatmap MD5");
-                    ui.label(optional_string(&self.data.beatmap_md5));
-                    ui.end_row();
-
-                    ui.label("Replay MD5");
-                    ui.label(optional_string(&self.data.replay_md5));
-                    ui.end_row();
-
-                    ui.label("Player Name");
-                    ui.label(self.data.player_name.clone().unwrap_or_default());
-                    ui.end_row();
-
-                    ui.label("300s");
-                    ui.label(self.data.hits_300.to_string());
-                    ui.end_row();
-
-                    ui.label("100s");
-                    ui.label(self.data.hits_100.to_string());
-                    ui.end_row();
-
-                    ui.label("50s");
-                    ui.label(self.data.hits_50.to_string());
-                    ui.end_row();
-
-                    ui.label("Gekis");
-                    ui.label(self.data.hits_geki.to_string());
-                    ui.end_row();
-
-                    ui.label("Katus");
-                    ui.label(self.data.hits_katu.to_string());
-                    ui.end_row();
-
-                    ui.label("Misses");
-                    ui.label(self.data.misses.to_string());
-                    ui.end_row();
-
-                    ui.label("Score");
-                    ui.label(self.data.score.to_string());
-                    ui.end_row();
-
-                    ui.label("Max Combo");
-                    ui.label(self.data.max_combo.to_string());
-                    ui.end_row();
-
-                    ui.label("Is Perfect Combo");
-                    ui.label(self.data.is_perfect_combo.to_string());
-                    ui.end_row();
+                if ui.link("Copy Summary").clicked() {
+                    let beatmap = beatmap_md5.and_then(|md5| beatmaps.get(md5));
+                    ui.ctx().copy_text(self.data.summary_line(beatmap));
+                }
+            });
 
-                    ui.label("Mods");
+            egui::Grid::new(self.id.with("grid")).show(ui, |ui| {
+                ui.label("Gameplay Mode");
+                ui.label(self.data.gameplay_mode.to_string());
+                ui.end_row();
+
+                ui.label("Version");
+                ui.label(version_string(self.data.version));
+                ui.end_row();
+
+                ui.label("Beatmap MD5");
+                ui.label(optional_string(&self.data.beatmap_md5));
+                ui.end_row();
+
+                ui.label("Replay MD5");
+                ui.label(optional_string(&self.data.replay_md5));
+                ui.end_row();
+
+                ui.label("Player Name");
+                ui.label(self.data.player_name.clone().unwrap_or_default());
+                ui.end_row();
+
+                for (hit_type, count) in [
+                    (HitType::Hit300, self.data.hits_300),
+                    (HitType::Hit100, self.data.hits_100),
+                    (HitType::Hit50, self.data.hits_50),
+                    (HitType::Geki, self.data.hits_geki),
+                    (HitType::Katu, self.data.hits_katu),
+                    (HitType::Miss, self.data.misses),
+                ] {
+                    ui.label(hit_label(self.data.gameplay_mode, hit_type));
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+
+                ui.label("Score");
+                ui.label(self.data.score.to_string());
+                ui.end_row();
+
+                ui.label("Max Combo");
+                ui.label(self.data.max_combo.to_string());
+                ui.end_row();
+
+                ui.label("Is Perfect Combo");
+                ui.label(self.data.is_perfect_combo.to_string());
+                ui.end_row();
+
+                ui.label("Mods");
+                ui.horizontal(|ui| {
                     ui.label(mods_string(self.data.mods));
-                    ui.end_row();
 
-                    ui.label("Lifebar Graph");
-
-                    if let Some(lifebar) = &self.data.lifebar_graph {
-                        use egui::Color32;
-                        use egui_plot::{Line, PlotPoints};
-
-                        let plot_points = lifebar
-                            .points
-                            .iter()
-                            .map(|(t, h)| [f64::from(*t), f64::from(*h)])
-                            .collect::<PlotPoints>();
-
-                        let line = Line::new(plot_points).color(Color32::WHITE).width(2.0);
-
-                        egui_plot::Plot::new(self.id.with("lifebar_plot"))
-                            .allow_drag(false)
-                            .allow_scroll(false)
-                            .allow_zoom(false)
-                            .allow_boxed_zoom(false)
-                            .show_axes([false, false])
-                            .show_x(false)
-                            .show_y(false)
-                            .include_x(0.0)
-                            .include_y(0.0)
-                            .include_y(1.0)
-                            .show(ui, |plot_ui| plot_ui.line(line));
-                    } else {
-                        ui.label(egui::RichText::new("N/A").italics());
+                    if self.show_raw_mods {
+                        ui.monospace(format!("0x{:08X}", self.data.mods.bits()));
                     }
 
-                    ui.end_row();
+                    ui.checkbox(&mut self.show_raw_mods, "Raw");
+                });
+                ui.end_row();
+
+                ui.label("Lifebar Graph");
+
+                if let Some(lifebar) = &self.data.lifebar_graph {
+                    let line = lifebar_line(lifebar).color(egui::Color32::WHITE);
+
+                    lifebar_plot(self.id.with("lifebar_plot"))
+                        .show_x(true)
+                        .show_y(true)
+                        .label_formatter(|_, cursor| {
+                            nearest_lifebar_point(lifebar, cursor.x)
+                                .map(|(time, hp)| {
+                                    format!("{}\nHP: {:.0}%", time_string(time), hp * 100.0)
+                                })
+                                .unwrap_or_default()
+                        })
+                        .show(ui, |plot_ui| plot_ui.line(line));
+                } else {
+                    ui.label(egui::RichText::new("N/A").italics());
+                }
+
+                ui.end_row();
+
+                ui.label("Timestamp");
+                ui.label(self.data.timestamp.to_string());
+                ui.end_row();
+
+                ui.label("Has Replay Data");
+                ui.label(
+                    self.data
+                        .replay_data
+                        .as_ref()
+                        .is_some_and(|data| !data.is_empty())
+                        .to_string(),
+                );
+                ui.end_row();
+
+                ui.label("Online Score ID");
+                ui.label(optional_string(&self.data.online_score_id_opt()));
+                ui.end_row();
+
+                ui.label("Additional Mod Information");
+                ui.label(optional_string(&self.data.additional_mod_info));
+                ui.end_row();
+            });
+        });
+    }
+}
 
-                    ui.label("Timestamp");
-                    ui.label(self.data.timestamp.to_string());
-                    ui.end_row();
+/// Creates a plot for displaying lifebar graphs, with HP ranging from 0 to 1 and interaction disabled.
+pub fn lifebar_plot<'a>(id: Id) -> egui_plot::Plot<'a> {
+    egui_plot::Plot::new(id)
+        .allow_drag(false)
+        .allow_scroll(false)
+        .allow_zoom(false)
+        .allow_boxed_zoom(false)
+        .show_axes([false, false])
+        .show_x(false)
+        .show_y(false)
+        .include_x(0.0)
+        .include_y(0.0)
+        .include_y(1.0)
+}
 
-                    ui.label("Has Replay Data");
-                    ui.label(
-                        self.data
-                            .replay_data
-                            .as_ref()
-                            .is_some_and(|data| !data.is_empty())
-                            .to_string(),
-                    );
-                    ui.end_row();
+/// Creates a line for plotting a lifebar graph. The colour is left to the caller.
+pub fn lifebar_line(lifebar: &LifebarGraph) -> egui_plot::Line {
+    let plot_points = lifebar
+        .points
+        .iter()
+        .map(|(t, h)| [f64::from(*t), f64::from(*h)])
+        .collect::<egui_plot::PlotPoints>();
 
-                    ui.label("Online Score ID");
-                    ui.label(maybe_signed_u64(self.data.online_score_id));
-                    ui.end_row();
+    egui_plot::Line::new(plot_points).width(2.0)
+}
 
-                    ui.label("Additional Mod Information");
-                    ui.label(optional_string(&self.data.additional_mod_info));
-                    ui.end_row();
-                });
-            });
-    }
+/// Finds the lifebar graph point whose time is closest to `time`.
+fn nearest_lifebar_point(lifebar: &LifebarGraph, time: f64) -> Option<(u32, f32)> {
+    // Points are in chronological order, so only the points either side of the split need to be checked
+    let split = lifebar
+        .points
+        .partition_point(|&(t, _)| f64::from(t) < time);
+
+    lifebar.points[split.saturating_sub(1)..]
+        .iter()
+        .take(2)
+        .min_by(|(a, _), (b, _)| {
+            (f64::from(*a) - time)
+                .abs()
+                .total_cmp(&(f64::from(*b) - time).abs())
+        })
+        .copied()
+}
+
+/// Formats a time in milliseconds as `mm:ss`.
+fn time_string(time: u32) -> String {
+    let seconds = time / 1000;
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
 }
@@ -0,0 +1,101 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::widgets::file_dialog::FileDialog;
+
+/// Holds the user's settings for the viewer, which are persisted between sessions.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// The osu! installation folder, which contains the database files.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub osu_folder: Option<PathBuf>,
+
+    /// The osu! `Songs` folder, which beatmap folder names are relative to.
+    ///
+    /// If this isn't set, the `Songs` folder in the osu! installation folder is used instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub songs_folder: Option<PathBuf>,
+}
+
+impl Settings {
+    /// The key used to persist the settings in eframe's storage.
+    pub const STORAGE_KEY: &'static str = "settings";
+
+    /// Loads the persisted settings, falling back to the defaults if there aren't any.
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value(storage, Self::STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    /// Gets the `Songs` folder to use, if one has been configured.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn songs_folder(&self) -> Option<PathBuf> {
+        self.songs_folder.clone().or_else(|| {
+            self.osu_folder
+                .as_ref()
+                .map(|osu_folder| osu_folder.join("Songs"))
+        })
+    }
+
+    /// Renders a window for editing these settings. Folders are picked through `file_dialog`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn view(&mut self, ctx: &egui::Context, open: &mut bool, file_dialog: &FileDialog) {
+        egui::Window::new("Settings")
+            .open(open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings_grid").show(ui, |ui| {
+                    ui.label("osu! Folder");
+                    folder_picker(ui, &mut self.osu_folder, file_dialog, "Not set");
+                    ui.end_row();
+
+                    let default_songs_folder = self
+                        .osu_folder
+                        .as_ref()
+                        .map(|osu_folder| osu_folder.join("Songs").display().to_string());
+
+                    ui.label("Songs Folder");
+                    folder_picker(
+                        ui,
+                        &mut self.songs_folder,
+                        file_dialog,
+                        default_songs_folder.as_deref().unwrap_or("Not set"),
+                    );
+                    ui.end_row();
+                });
+            });
+    }
+}
+
+/// Renders the path to a folder, along with buttons for picking and clearing it.
+#[cfg(not(target_arch = "wasm32"))]
+fn folder_picker(
+    ui: &mut egui::Ui,
+    folder: &mut Option<PathBuf>,
+    file_dialog: &FileDialog,
+    placeholder: &str,
+) {
+    ui.horizontal(|ui| {
+        if let Some(folder) = folder {
+            ui.label(folder.display().to_string());
+        } else {
+            ui.label(egui::RichText::new(placeholder).italics());
+        }
+
+        if ui.button("Browse...").clicked() {
+            if let Some(picked) = file_dialog.pick_folder() {
+                *folder = Some(picked);
+            }
+        }
+
+        if ui
+            .add_enabled(folder.is_some(), egui::Button::new("Clear"))
+            .clicked()
+        {
+            *folder = None;
+        }
+    });
+}
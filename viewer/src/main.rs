@@ -10,7 +10,7 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "osu! Database Viewer",
         eframe::NativeOptions::default(),
-        Box::new(|_| Ok(Box::new(app::MainApp::default()))),
+        Box::new(|cc| Ok(Box::new(app::MainApp::new(cc)))),
     )
 }
 
@@ -39,7 +39,7 @@ fn main() {
             .start(
                 canvas,
                 eframe::WebOptions::default(),
-                Box::new(|_| Ok(Box::new(app::MainApp::default()))),
+                Box::new(|cc| Ok(Box::new(app::MainApp::new(cc)))),
             )
             .await
             .expect("failed to start eframe");
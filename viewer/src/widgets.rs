@@ -1,3 +1,5 @@
+pub mod date_range;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub mod file_dialog;
 
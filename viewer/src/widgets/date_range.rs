@@ -0,0 +1,46 @@
+//! A date range filter, with optional start and end dates entered as text.
+
+use time::{macros::format_description, Date, OffsetDateTime};
+
+#[derive(Default)]
+pub struct DateRangeFilter {
+    from: String,
+    to: String,
+}
+
+impl DateRangeFilter {
+    /// Renders the inputs for the start and end dates (YYYY-MM-DD).
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("From");
+            Self::date_input(ui, &mut self.from);
+            ui.label("To");
+            Self::date_input(ui, &mut self.to);
+        });
+    }
+
+    /// Checks if a timestamp falls within this range. Both ends are inclusive, and any missing or invalid dates are treated as unbounded.
+    pub fn contains(&self, timestamp: OffsetDateTime) -> bool {
+        let date = timestamp.date();
+
+        Self::parse_date(&self.from).is_none_or(|from| date >= from)
+            && Self::parse_date(&self.to).is_none_or(|to| date <= to)
+    }
+
+    fn date_input(ui: &mut egui::Ui, value: &mut String) {
+        let is_valid = value.is_empty() || Self::parse_date(value).is_some();
+        let mut input = egui::TextEdit::singleline(value)
+            .hint_text("YYYY-MM-DD")
+            .desired_width(80.0);
+
+        if !is_valid {
+            input = input.text_color(ui.visuals().error_fg_color);
+        }
+
+        ui.add(input);
+    }
+
+    fn parse_date(value: &str) -> Option<Date> {
+        Date::parse(value.trim(), format_description!("[year]-[month]-[day]")).ok()
+    }
+}
@@ -4,14 +4,11 @@
 
 use rfd;
 
+#[derive(Default)]
 pub struct FileDialog {
     file: Option<Vec<u8>>,
-}
-
-impl Default for FileDialog {
-    fn default() -> Self {
-        Self { file: None }
-    }
+    folder_files: Option<Vec<(String, Vec<u8>)>>,
+    files: Option<Vec<(String, Vec<u8>)>>,
 }
 
 impl FileDialog {
@@ -22,7 +19,87 @@ impl FileDialog {
         }
     }
 
+    /// Picks a folder and reads any of the specified files which are present in it. See [`FileDialog::read_folder`].
+    pub fn open_folder(&mut self, file_names: &[&[&str]]) {
+        let folder = rfd::FileDialog::new().pick_folder();
+        if let Some(folder) = folder {
+            self.read_folder(&folder, file_names);
+        }
+    }
+
+    /// Reads any of the specified files which are present in a folder, without showing a dialog.
+    ///
+    /// Each entry in `file_names` lists alternative names for the same file; only the first one that can be read is used.
+    pub fn read_folder(&mut self, folder: &std::path::Path, file_names: &[&[&str]]) {
+        let files = file_names
+            .iter()
+            .filter_map(|alternatives| {
+                alternatives.iter().find_map(|file_name| {
+                    let path = folder.join(file_name);
+                    match std::fs::read(&path) {
+                        Ok(data) => Some((file_name.to_string(), data)),
+                        Err(e) => {
+                            log::info!("Skipping '{}': {}", path.display(), e);
+                            None
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        self.folder_files = Some(files);
+    }
+
+    /// Picks multiple files with the specified extensions and reads each of them.
+    pub fn open_multiple(&mut self, filter_name: &str, extensions: &[&str]) {
+        let paths = rfd::FileDialog::new()
+            .add_filter(filter_name, extensions)
+            .pick_files();
+
+        if let Some(paths) = paths {
+            let files = paths
+                .into_iter()
+                .filter_map(|path| {
+                    let file_name = path.file_name()?.to_string_lossy().into_owned();
+                    match std::fs::read(&path) {
+                        Ok(data) => Some((file_name, data)),
+                        Err(e) => {
+                            log::warn!("Unable to read '{}': {}", path.display(), e);
+                            None
+                        }
+                    }
+                })
+                .collect();
+
+            self.files = Some(files);
+        }
+    }
+
+    /// Picks a folder, returning its path without reading any files.
+    pub fn pick_folder(&self) -> Option<std::path::PathBuf> {
+        rfd::FileDialog::new().pick_folder()
+    }
+
+    pub fn save(&self, file_name: &str, data: &[u8]) {
+        let path = rfd::FileDialog::new().set_file_name(file_name).save_file();
+        if let Some(path) = path {
+            if let Err(e) = std::fs::write(&path, data) {
+                log::error!("Unable to save file '{}': {}", path.display(), e);
+            }
+        }
+    }
+
     pub fn get(&mut self) -> Option<Vec<u8>> {
-        std::mem::replace(&mut self.file, None)
+        self.file.take()
+    }
+
+    /// Gets the names and contents of the files read by [`FileDialog::open_folder`], once a folder has been picked.
+    pub fn get_folder(&mut self) -> Option<Vec<(String, Vec<u8>)>> {
+        self.folder_files.take()
+    }
+
+    /// Gets the names and contents of the files read by [`FileDialog::open_multiple`], once they have been picked.
+    pub fn get_multiple(&mut self) -> Option<Vec<(String, Vec<u8>)>> {
+        self.files.take()
     }
 }
@@ -2,14 +2,22 @@
 //!
 //! Based on the implementation from [kirjavascript/trueLMAO](https://github.com/kirjavascript/trueLMAO/tree/master).
 
-use js_sys::{ArrayBuffer, Uint8Array};
+use std::{cell::RefCell, rc::Rc};
+
+use js_sys::{Array, ArrayBuffer, Uint8Array};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{window, FileReader, HtmlInputElement};
+use web_sys::{window, Blob, File, FileReader, HtmlAnchorElement, HtmlInputElement, Url};
+
+type NamedFiles = Vec<(String, Vec<u8>)>;
 
 pub struct FileDialog {
     tx: std::sync::mpsc::Sender<Vec<u8>>,
     rx: std::sync::mpsc::Receiver<Vec<u8>>,
+    folder_tx: std::sync::mpsc::Sender<NamedFiles>,
+    folder_rx: std::sync::mpsc::Receiver<NamedFiles>,
+    files_tx: std::sync::mpsc::Sender<NamedFiles>,
+    files_rx: std::sync::mpsc::Receiver<NamedFiles>,
     input: HtmlInputElement,
     closure: Option<Closure<dyn FnMut()>>,
 }
@@ -17,6 +25,8 @@ pub struct FileDialog {
 impl Default for FileDialog {
     fn default() -> Self {
         let (tx, rx) = std::sync::mpsc::channel();
+        let (folder_tx, folder_rx) = std::sync::mpsc::channel();
+        let (files_tx, files_rx) = std::sync::mpsc::channel();
 
         let document = window().unwrap().document().unwrap();
         let body = document.body().unwrap();
@@ -32,6 +42,10 @@ impl Default for FileDialog {
         Self {
             rx,
             tx,
+            folder_rx,
+            folder_tx,
+            files_rx,
+            files_tx,
             input,
             closure: None,
         }
@@ -49,36 +63,88 @@ impl Drop for FileDialog {
 
 impl FileDialog {
     pub fn open(&mut self) {
-        if let Some(closure) = &self.closure {
-            self.input
-                .remove_event_listener_with_callback("change", closure.as_ref().unchecked_ref())
-                .unwrap();
-            std::mem::replace(&mut self.closure, None).unwrap().forget();
-        }
-
         let tx = self.tx.clone();
         let input_clone = self.input.clone();
 
         let closure = Closure::once(move || {
             if let Some(file) = input_clone.files().and_then(|files| files.get(0)) {
-                let reader = FileReader::new().unwrap();
-                let reader_clone = reader.clone();
-                let onload_closure = Closure::once(Box::new(move || {
-                    let array_buffer = reader_clone
-                        .result()
-                        .unwrap()
-                        .dyn_into::<ArrayBuffer>()
-                        .unwrap();
-                    let buffer = Uint8Array::new(&array_buffer).to_vec();
+                read_file(&file, move |buffer| {
                     tx.send(buffer).ok();
-                }));
+                });
+            }
+        });
+
+        self.input.set_multiple(false);
+        self.input.set_accept("");
+        self.show(closure);
+    }
+
+    /// Lets the user select the database files from their osu! folder.
+    ///
+    /// Browsers can't read files from a folder directly, so this falls back to selecting multiple files; the file names are ignored.
+    pub fn open_folder(&mut self, _file_names: &[&[&str]]) {
+        self.input.set_accept("");
+        self.open_many(self.folder_tx.clone());
+    }
+
+    /// Lets the user select multiple files with the specified extensions, reading each of them.
+    pub fn open_multiple(&mut self, _filter_name: &str, extensions: &[&str]) {
+        let accept = extensions
+            .iter()
+            .map(|extension| format!(".{}", extension))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.input.set_accept(&accept);
+        self.open_many(self.files_tx.clone());
+    }
+
+    /// Shows a multi-select file dialog, sending the names and contents of the selected files to `tx` once all of them have been read.
+    fn open_many(&mut self, tx: std::sync::mpsc::Sender<NamedFiles>) {
+        let input_clone = self.input.clone();
+
+        let closure = Closure::once(move || {
+            let Some(files) = input_clone.files() else {
+                return;
+            };
+
+            // Files are read asynchronously, so only send them once all of them have been read
+            let count = files.length() as usize;
+            let loaded = Rc::new(RefCell::new(Vec::with_capacity(count)));
+
+            if count == 0 {
+                tx.send(Vec::new()).ok();
+            }
+
+            for file in (0..files.length()).filter_map(|i| files.get(i)) {
+                let name = file.name();
+                let tx = tx.clone();
+                let loaded = Rc::clone(&loaded);
+
+                read_file(&file, move |buffer| {
+                    let mut loaded = loaded.borrow_mut();
+                    loaded.push((name, buffer));
 
-                reader.set_onload(Some(onload_closure.as_ref().unchecked_ref()));
-                reader.read_as_array_buffer(&file).unwrap();
-                onload_closure.forget();
+                    if loaded.len() == count {
+                        tx.send(std::mem::take(&mut *loaded)).ok();
+                    }
+                });
             }
         });
 
+        self.input.set_multiple(true);
+        self.show(closure);
+    }
+
+    /// Shows the file selection dialog, replacing any previous change listener.
+    fn show(&mut self, closure: Closure<dyn FnMut()>) {
+        if let Some(closure) = &self.closure {
+            self.input
+                .remove_event_listener_with_callback("change", closure.as_ref().unchecked_ref())
+                .unwrap();
+            std::mem::replace(&mut self.closure, None).unwrap().forget();
+        }
+
         self.input
             .add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())
             .unwrap();
@@ -86,6 +152,27 @@ impl FileDialog {
         self.input.click();
     }
 
+    pub fn save(&self, file_name: &str, data: &[u8]) {
+        // Browsers can't show a save dialog directly, so download the data through a temporary link instead
+        let parts = Array::of1(&Uint8Array::from(data));
+        let blob = Blob::new_with_u8_array_sequence(&parts).unwrap();
+        let url = Url::create_object_url_with_blob(&blob).unwrap();
+
+        let anchor = window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("a")
+            .unwrap()
+            .dyn_into::<HtmlAnchorElement>()
+            .unwrap();
+        anchor.set_href(&url);
+        anchor.set_download(file_name);
+        anchor.click();
+
+        Url::revoke_object_url(&url).unwrap();
+    }
+
     pub fn get(&self) -> Option<Vec<u8>> {
         if let Ok(file) = self.rx.try_recv() {
             Some(file)
@@ -93,4 +180,32 @@ impl FileDialog {
             None
         }
     }
+
+    /// Gets the names and contents of the files selected through [`FileDialog::open_folder`], once all of them have been read.
+    pub fn get_folder(&self) -> Option<NamedFiles> {
+        self.folder_rx.try_recv().ok()
+    }
+
+    /// Gets the names and contents of the files selected through [`FileDialog::open_multiple`], once all of them have been read.
+    pub fn get_multiple(&self) -> Option<NamedFiles> {
+        self.files_rx.try_recv().ok()
+    }
+}
+
+/// Reads the contents of a file, passing them to `on_load` once they're available.
+fn read_file(file: &File, on_load: impl FnOnce(Vec<u8>) + 'static) {
+    let reader = FileReader::new().unwrap();
+    let reader_clone = reader.clone();
+    let onload_closure = Closure::once(Box::new(move || {
+        let array_buffer = reader_clone
+            .result()
+            .unwrap()
+            .dyn_into::<ArrayBuffer>()
+            .unwrap();
+        on_load(Uint8Array::new(&array_buffer).to_vec());
+    }));
+
+    reader.set_onload(Some(onload_closure.as_ref().unchecked_ref()));
+    reader.read_as_array_buffer(file).unwrap();
+    onload_closure.forget();
 }